@@ -0,0 +1,134 @@
+use std::cmp::Ordering;
+
+use datafusion::error::Result;
+use datafusion::logical_expr::interval_arithmetic::Interval;
+use datafusion::scalar::ScalarValue;
+
+use crate::greatest::NullHandling;
+
+/// Picks the extreme bound in the `target` direction. A null (unbounded) bound either wins
+/// (`unbounded_wins`, e.g. upper bounds of greatest()) or is ignored (e.g. lower bounds of greatest()).
+fn extreme_bound<'a>(
+    bounds: impl Iterator<Item = &'a ScalarValue>,
+    target: Ordering,
+    unbounded_wins: bool,
+) -> Option<ScalarValue> {
+    let mut best: Option<&ScalarValue> = None;
+    for bound in bounds {
+        if bound.is_null() {
+            if unbounded_wins {
+                return None;
+            }
+            continue;
+        }
+        if best.map_or(true, |best| bound.partial_cmp(best) == Some(target)) {
+            best = Some(bound);
+        }
+    }
+    best.cloned()
+}
+
+/// Output interval of greatest() (`target` is `Ordering::Greater`) or least() (`Ordering::Less`).
+///
+/// When nulls are propagated every input takes part in the result, so it is `[max(lo_i), max(hi_i)]`
+/// and `[min(lo_i), min(hi_i)]` respectively. When nulls are skipped any input can be the only not null
+/// one (`greatest(NULL, 5) = 5`), the intervals don't tell which inputs are never null,
+/// so the result is only known to be within `[min(lo_i), max(hi_i)]`.
+pub(crate) fn extremum_bounds(
+    inputs: &[&Interval],
+    target: Ordering,
+    null_handling: NullHandling,
+) -> Result<Interval> {
+    let datatype = inputs[0].data_type();
+    let unbounded = || ScalarValue::try_from(&datatype);
+
+    let (lower_target, upper_target) = match null_handling {
+        NullHandling::Propagate => (target, target),
+        NullHandling::Skip => (Ordering::Less, Ordering::Greater),
+    };
+    let lower = extreme_bound(
+        inputs.iter().map(|i| i.lower()),
+        lower_target,
+        lower_target == Ordering::Less,
+    );
+    let upper = extreme_bound(
+        inputs.iter().map(|i| i.upper()),
+        upper_target,
+        upper_target == Ordering::Greater,
+    );
+
+    Interval::try_new(
+        lower.map_or_else(unbounded, Ok)?,
+        upper.map_or_else(unbounded, Ok)?,
+    )
+}
+
+/// Narrows the input intervals given the output `interval` of greatest() or least().
+///
+/// For greatest() with the output in `[lo, hi]` every input is at most `hi`, and at least one input
+/// is at least `lo`. If only one input can reach `lo`, it is that one. least() is symmetric.
+/// An input interval bounds the input's not null values. When nulls are skipped, an input without
+/// a value in the bound can still be null, so it keeps its interval instead of making the output infeasible.
+/// Returns `None` if no input can produce a value in the output interval.
+pub(crate) fn propagate_extremum_constraints(
+    interval: &Interval,
+    inputs: &[&Interval],
+    target: Ordering,
+    null_handling: NullHandling,
+) -> Result<Option<Vec<Interval>>> {
+    let datatype = interval.data_type();
+    let unbounded = ScalarValue::try_from(&datatype)?;
+
+    // the bound that holds for every input, and the bound that holds for at least one of them
+    let (all_bound, any_bound) = match target {
+        Ordering::Greater => (
+            Interval::try_new(unbounded.clone(), interval.upper().clone())?,
+            Interval::try_new(interval.lower().clone(), unbounded)?,
+        ),
+        _ => (
+            Interval::try_new(interval.lower().clone(), unbounded.clone())?,
+            Interval::try_new(unbounded, interval.upper().clone())?,
+        ),
+    };
+
+    // None for an input that has to be null, which is possible only when nulls are skipped
+    let mut narrowed = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        match input.intersect(&all_bound)? {
+            Some(input) => narrowed.push(Some(input)),
+            None if null_handling == NullHandling::Skip => narrowed.push(None),
+            None => return Ok(None),
+        }
+    }
+
+    let candidates = narrowed
+        .iter()
+        .enumerate()
+        .filter(|(_, input)| {
+            input
+                .as_ref()
+                .is_some_and(|input| matches!(input.intersect(&any_bound), Ok(Some(_))))
+        })
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+
+    match candidates.as_slice() {
+        [] => return Ok(None),
+        [single] => {
+            narrowed[*single] = narrowed[*single]
+                .as_ref()
+                .and_then(|input| input.intersect(&any_bound).transpose())
+                .transpose()?
+        }
+        _ => {}
+    }
+
+    // the interval of an input that has to be null can't be narrowed
+    Ok(Some(
+        narrowed
+            .into_iter()
+            .zip(inputs)
+            .map(|(narrowed, input)| narrowed.unwrap_or_else(|| (*input).clone()))
+            .collect(),
+    ))
+}
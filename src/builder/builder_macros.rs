@@ -1,22 +1,122 @@
 /// This macro is used to implement the `AppendableBuilder` trait for a primitive builder types.
+/// The bulk appends go through `BulkAppend`/`BulkAppendValue` of the underlying arrow builder.
+/// Builders of types with parameters (decimals, timestamps) don't report them, so the trait is implemented for
+/// the `TypedBuilder` that keeps their data type, and scalars matched by their value with `$scalar(v, ..)`
+/// are checked against it first: a decimal of another precision or a timestamp in another timezone is a `TypeMismatch`.
 #[macro_export]
 macro_rules! impl_builder_append {
     ($builder:ty, $scalar:ident) => {
         impl AppendableBuilder for $builder {
+            fn data_type(&self) -> datafusion::arrow::datatypes::DataType {
+                <Self as $crate::builder::typed::FixedDataType>::fixed_data_type()
+            }
+
             fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
                 match value {
                     ScalarValue::$scalar(Some(v)) => self.append_value(v),
-                    ScalarValue::$scalar(None) => self.append_null(),
-                    ScalarValue::Null => self.append_null(),
-                    _ => {
-                        return Err(datafusion::error::DataFusionError::Internal(format!(
-                            "Invalid ScalarValue for {}",
-                            stringify!($builder)
-                        )))
+                    ScalarValue::$scalar(None) | ScalarValue::Null => self.append_null(),
+                    value => {
+                        return Err($crate::builder::typed::type_mismatch(
+                            AppendableBuilder::data_type(self),
+                            &value,
+                        ))
+                    }
+                }
+                Ok(())
+            }
+
+            fn append_scalar_n(&mut self, value: ScalarValue, n: usize) -> Result<()> {
+                match value {
+                    ScalarValue::$scalar(Some(v)) => {
+                        $crate::builder::bulk::BulkAppendValue::bulk_append_value_n(self, v, n)
+                    }
+                    ScalarValue::$scalar(None) | ScalarValue::Null => {
+                        $crate::builder::bulk::BulkAppend::bulk_append_nulls(self, n)
+                    }
+                    value => {
+                        return Err($crate::builder::typed::type_mismatch(
+                            AppendableBuilder::data_type(self),
+                            &value,
+                        ))
+                    }
+                }
+                Ok(())
+            }
+
+            fn append_nulls(&mut self, n: usize) -> Result<()> {
+                $crate::builder::bulk::BulkAppend::bulk_append_nulls(self, n);
+                Ok(())
+            }
+
+            fn append_array_slice(
+                &mut self,
+                array: &datafusion::arrow::array::ArrayRef,
+                offset: usize,
+                len: usize,
+            ) -> Result<()> {
+                $crate::builder::bulk::check_slice(array.as_ref(), offset, len)?;
+                $crate::builder::bulk::BulkAppend::bulk_append_slice(
+                    self,
+                    array.as_ref(),
+                    offset,
+                    len,
+                )
+            }
+        }
+    };
+    ($builder:ty, $scalar:ident, ..) => {
+        impl AppendableBuilder for $crate::builder::typed::TypedBuilder<$builder> {
+            fn data_type(&self) -> datafusion::arrow::datatypes::DataType {
+                self.data_type.clone()
+            }
+
+            fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
+                self.append_scalar_n(value, 1)
+            }
+
+            fn append_scalar_n(&mut self, value: ScalarValue, n: usize) -> Result<()> {
+                self.check(&value.data_type())?;
+                match value {
+                    ScalarValue::$scalar(Some(v), ..) => {
+                        $crate::builder::bulk::BulkAppendValue::bulk_append_value_n(
+                            &mut self.builder,
+                            v,
+                            n,
+                        )
+                    }
+                    ScalarValue::$scalar(None, ..) | ScalarValue::Null => {
+                        $crate::builder::bulk::BulkAppend::bulk_append_nulls(&mut self.builder, n)
+                    }
+                    value => {
+                        return Err($crate::builder::typed::type_mismatch(
+                            self.data_type.clone(),
+                            &value,
+                        ))
                     }
                 }
                 Ok(())
             }
+
+            fn append_nulls(&mut self, n: usize) -> Result<()> {
+                $crate::builder::bulk::BulkAppend::bulk_append_nulls(&mut self.builder, n);
+                Ok(())
+            }
+
+            fn append_array_slice(
+                &mut self,
+                array: &datafusion::arrow::array::ArrayRef,
+                offset: usize,
+                len: usize,
+            ) -> Result<()> {
+                $crate::builder::bulk::check_slice(array.as_ref(), offset, len)?;
+                self.check(array.data_type())?;
+                $crate::builder::bulk::BulkAppend::bulk_append_slice(
+                    &mut self.builder,
+                    array.as_ref(),
+                    offset,
+                    len,
+                )
+            }
         }
     };
 }
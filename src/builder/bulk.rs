@@ -0,0 +1,190 @@
+use datafusion::arrow::array::{
+    Array, ArrowPrimitiveType, AsArray, BooleanBuilder, FixedSizeBinaryBuilder, GenericByteBuilder,
+    GenericByteViewBuilder, PrimitiveBuilder,
+};
+use datafusion::arrow::datatypes::{ByteArrayType, ByteViewType};
+use datafusion::error::Result;
+
+use super::typed::{array_type_mismatch, FixedDataType, TypedBuilder};
+
+/// Bulk appends of the flat arrow builders, used by the `AppendableBuilder` implementations.
+/// Primitive and boolean builders copy a slice of values at once, byte view builders share the data buffers
+/// of the slice and append only its views. arrow 52 doesn't expose the value and offset buffers of the byte
+/// and fixed size binary builders, so their slices are appended value by value.
+pub(crate) trait BulkAppend {
+    fn bulk_append_nulls(&mut self, n: usize);
+
+    /// appends `len` values of `array` starting at `offset`, the range is already checked
+    fn bulk_append_slice(&mut self, array: &dyn Array, offset: usize, len: usize) -> Result<()>;
+}
+
+/// Appending the same value `n` times, `V` is the payload of the builder's `ScalarValue`
+pub(crate) trait BulkAppendValue<V> {
+    fn bulk_append_value_n(&mut self, value: V, n: usize);
+}
+
+/// Returns an error if `offset..offset + len` is out of bounds of `array`
+pub(crate) fn check_slice(array: &dyn Array, offset: usize, len: usize) -> Result<()> {
+    if offset
+        .checked_add(len)
+        .map_or(true, |end| end > array.len())
+    {
+        return Err(datafusion::error::DataFusionError::Internal(format!(
+            "Slice {offset}..{} is out of bounds of an array of length {}",
+            offset.saturating_add(len),
+            array.len()
+        )));
+    }
+    Ok(())
+}
+
+impl<T: ArrowPrimitiveType> BulkAppend for PrimitiveBuilder<T> {
+    fn bulk_append_nulls(&mut self, n: usize) {
+        self.append_nulls(n);
+    }
+
+    fn bulk_append_slice(&mut self, array: &dyn Array, offset: usize, len: usize) -> Result<()> {
+        let array = array
+            .as_primitive_opt::<T>()
+            .ok_or_else(|| array_type_mismatch(Self::fixed_data_type(), array))?;
+        let values = &array.values()[offset..offset + len];
+        match array.nulls() {
+            Some(nulls) if nulls.null_count() > 0 => {
+                let is_valid = nulls.slice(offset, len).iter().collect::<Vec<_>>();
+                self.append_values(values, &is_valid);
+            }
+            _ => self.append_slice(values),
+        }
+        Ok(())
+    }
+}
+
+impl<T: ArrowPrimitiveType> BulkAppendValue<T::Native> for PrimitiveBuilder<T> {
+    fn bulk_append_value_n(&mut self, value: T::Native, n: usize) {
+        self.append_slice(&vec![value; n]);
+    }
+}
+
+impl BulkAppend for BooleanBuilder {
+    fn bulk_append_nulls(&mut self, n: usize) {
+        self.append_nulls(n);
+    }
+
+    fn bulk_append_slice(&mut self, array: &dyn Array, offset: usize, len: usize) -> Result<()> {
+        let array = array
+            .as_boolean_opt()
+            .ok_or_else(|| array_type_mismatch(Self::fixed_data_type(), array))?
+            .slice(offset, len);
+        let values = array.values().iter().collect::<Vec<_>>();
+        match array.nulls() {
+            Some(nulls) => {
+                let is_valid = nulls.iter().collect::<Vec<_>>();
+                self.append_values(&values, &is_valid)?;
+            }
+            None => self.append_slice(&values),
+        }
+        Ok(())
+    }
+}
+
+impl BulkAppendValue<bool> for BooleanBuilder {
+    fn bulk_append_value_n(&mut self, value: bool, n: usize) {
+        self.append_slice(&vec![value; n]);
+    }
+}
+
+impl<T: ByteArrayType> BulkAppend for GenericByteBuilder<T> {
+    fn bulk_append_nulls(&mut self, n: usize) {
+        for _ in 0..n {
+            self.append_null();
+        }
+    }
+
+    fn bulk_append_slice(&mut self, array: &dyn Array, offset: usize, len: usize) -> Result<()> {
+        let array = array
+            .as_bytes_opt::<T>()
+            .ok_or_else(|| array_type_mismatch(Self::fixed_data_type(), array))?;
+        // per value fallback: the builder's buffers can't be extended with a rebased slice of offsets
+        for i in offset..offset + len {
+            if array.is_null(i) {
+                self.append_null();
+            } else {
+                self.append_value(array.value(i));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: ByteArrayType, V: AsRef<T::Native>> BulkAppendValue<V> for GenericByteBuilder<T> {
+    fn bulk_append_value_n(&mut self, value: V, n: usize) {
+        for _ in 0..n {
+            self.append_value(&value);
+        }
+    }
+}
+
+impl<T: ByteViewType> BulkAppend for GenericByteViewBuilder<T> {
+    fn bulk_append_nulls(&mut self, n: usize) {
+        for _ in 0..n {
+            self.append_null();
+        }
+    }
+
+    fn bulk_append_slice(&mut self, array: &dyn Array, offset: usize, len: usize) -> Result<()> {
+        let array = array
+            .as_byte_view_opt::<T>()
+            .ok_or_else(|| array_type_mismatch(Self::fixed_data_type(), array))?;
+        // the data buffers of the slice are shared with the builder, each of them once per slice, and the long values
+        // are appended as views into them. Values of up to 12 bytes are inlined in their views.
+        let mut blocks = vec![None; array.data_buffers().len()];
+        for i in offset..offset + len {
+            if array.is_null(i) {
+                self.append_null();
+                continue;
+            }
+            let view = array.views()[i];
+            let length = view as u32;
+            if length <= 12 {
+                self.append_value(array.value(i));
+                continue;
+            }
+            let buffer = (view >> 64) as u32 as usize;
+            let block = *blocks[buffer]
+                .get_or_insert_with(|| self.append_block(array.data_buffers()[buffer].clone()));
+            self.try_append_view(block, (view >> 96) as u32, length)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: ByteViewType, V: AsRef<T::Native>> BulkAppendValue<V> for GenericByteViewBuilder<T> {
+    fn bulk_append_value_n(&mut self, value: V, n: usize) {
+        for _ in 0..n {
+            self.append_value(&value);
+        }
+    }
+}
+
+/// The fixed size binary builder doesn't report its byte width, its type is read from the `TypedBuilder`
+impl BulkAppend for TypedBuilder<FixedSizeBinaryBuilder> {
+    fn bulk_append_nulls(&mut self, n: usize) {
+        for _ in 0..n {
+            self.builder.append_null();
+        }
+    }
+
+    fn bulk_append_slice(&mut self, array: &dyn Array, offset: usize, len: usize) -> Result<()> {
+        let array = array
+            .as_fixed_size_binary_opt()
+            .ok_or_else(|| array_type_mismatch(self.data_type.clone(), array))?;
+        for i in offset..offset + len {
+            if array.is_null(i) {
+                self.builder.append_null();
+            } else {
+                self.builder.append_value(array.value(i))?;
+            }
+        }
+        Ok(())
+    }
+}
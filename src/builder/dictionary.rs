@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use datafusion::arrow::array::{
+    ArrayBuilder, ArrayRef, AsArray, DictionaryArray, GenericByteDictionaryBuilder, PrimitiveArray,
+    PrimitiveBuilder, PrimitiveDictionaryBuilder,
+};
+use datafusion::arrow::datatypes::ArrowNativeType;
+use datafusion::arrow::datatypes::{
+    ArrowDictionaryKeyType, ArrowPrimitiveType, BinaryType, ByteArrayType, DataType, Date32Type,
+    Date64Type, Float16Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type,
+    LargeBinaryType, LargeUtf8Type, UInt16Type, UInt32Type, UInt64Type, UInt8Type, Utf8Type,
+};
+use datafusion::error::Result;
+use datafusion::scalar::ScalarValue;
+
+use super::{make_builder, scalar_from_array, type_mismatch, AppendableBuilder};
+
+/// Returns a dictionary builder for `Dictionary(key_type, value_type)`.
+/// Values are strings, binaries, primitives, string and binary views or decimals.
+/// The builders append slices one value at a time, with the `AppendableBuilder` defaults.
+pub(super) fn make_dictionary_builder(
+    key_type: &DataType,
+    value_type: &DataType,
+    capacity: usize,
+) -> Result<Box<dyn AppendableBuilder>> {
+    match key_type {
+        DataType::Int8 => make_values_builder::<Int8Type>(value_type, capacity),
+        DataType::Int16 => make_values_builder::<Int16Type>(value_type, capacity),
+        DataType::Int32 => make_values_builder::<Int32Type>(value_type, capacity),
+        DataType::Int64 => make_values_builder::<Int64Type>(value_type, capacity),
+        DataType::UInt8 => make_values_builder::<UInt8Type>(value_type, capacity),
+        DataType::UInt16 => make_values_builder::<UInt16Type>(value_type, capacity),
+        DataType::UInt32 => make_values_builder::<UInt32Type>(value_type, capacity),
+        DataType::UInt64 => make_values_builder::<UInt64Type>(value_type, capacity),
+        t => Err(datafusion::error::DataFusionError::Execution(format!(
+            "Dictionary key type {t:?} is not currently supported"
+        ))),
+    }
+}
+
+fn make_values_builder<K: ArrowDictionaryKeyType>(
+    value_type: &DataType,
+    capacity: usize,
+) -> Result<Box<dyn AppendableBuilder>> {
+    macro_rules! bytes {
+        ($t:ty) => {
+            Box::new(GenericByteDictionaryBuilder::<K, $t>::with_capacity(
+                capacity, capacity, 1024,
+            ))
+        };
+    }
+    macro_rules! primitive {
+        ($t:ty) => {
+            Box::new(PrimitiveDictionaryBuilder::<K, $t>::with_capacity(
+                capacity, capacity,
+            ))
+        };
+    }
+
+    Ok(match value_type {
+        DataType::Utf8 => bytes!(Utf8Type),
+        DataType::LargeUtf8 => bytes!(LargeUtf8Type),
+        DataType::Binary => bytes!(BinaryType),
+        DataType::LargeBinary => bytes!(LargeBinaryType),
+        DataType::Int8 => primitive!(Int8Type),
+        DataType::Int16 => primitive!(Int16Type),
+        DataType::Int32 => primitive!(Int32Type),
+        DataType::Int64 => primitive!(Int64Type),
+        DataType::UInt8 => primitive!(UInt8Type),
+        DataType::UInt16 => primitive!(UInt16Type),
+        DataType::UInt32 => primitive!(UInt32Type),
+        DataType::UInt64 => primitive!(UInt64Type),
+        DataType::Float16 => primitive!(Float16Type),
+        DataType::Float32 => primitive!(Float32Type),
+        DataType::Float64 => primitive!(Float64Type),
+        DataType::Date32 => primitive!(Date32Type),
+        DataType::Date64 => primitive!(Date64Type),
+        DataType::Utf8View
+        | DataType::BinaryView
+        | DataType::Decimal128(_, _)
+        | DataType::Decimal256(_, _) => {
+            Box::new(ValuesDictionaryBuilder::<K>::new(value_type, capacity)?)
+        }
+        t => {
+            return Err(datafusion::error::DataFusionError::Execution(format!(
+                "Dictionary value type {t:?} is not currently supported"
+            )))
+        }
+    })
+}
+
+/// Unwraps a dictionary scalar (or a plain scalar of the value type) into a single row array
+/// of the value type of `data_type`, `None` stands for a null.
+fn dictionary_value(value: ScalarValue, data_type: &DataType) -> Result<Option<ArrayRef>> {
+    let DataType::Dictionary(_, value_type) = data_type else {
+        return Err(datafusion::error::DataFusionError::Internal(format!(
+            "Data type {data_type:?} is not a dictionary"
+        )));
+    };
+    let inner = match &value {
+        ScalarValue::Dictionary(_, inner) => inner.as_ref(),
+        value => value,
+    };
+    if inner.is_null() {
+        return Ok(None);
+    }
+    if &inner.data_type() != value_type.as_ref() {
+        return Err(type_mismatch(data_type.clone(), &value));
+    }
+    inner.to_array().map(Some)
+}
+
+impl<K: ArrowDictionaryKeyType, T: ByteArrayType> AppendableBuilder
+    for GenericByteDictionaryBuilder<K, T>
+{
+    fn data_type(&self) -> DataType {
+        DataType::Dictionary(Box::new(K::DATA_TYPE), Box::new(T::DATA_TYPE))
+    }
+
+    fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
+        match dictionary_value(value, &self.data_type())? {
+            Some(array) => {
+                self.append(array.as_bytes::<T>().value(0))?;
+            }
+            None => self.append_null(),
+        }
+        Ok(())
+    }
+}
+
+impl<K: ArrowDictionaryKeyType, V: ArrowPrimitiveType> AppendableBuilder
+    for PrimitiveDictionaryBuilder<K, V>
+{
+    fn data_type(&self) -> DataType {
+        DataType::Dictionary(Box::new(K::DATA_TYPE), Box::new(V::DATA_TYPE))
+    }
+
+    fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
+        match dictionary_value(value, &self.data_type())? {
+            Some(array) => {
+                self.append(array.as_primitive::<V>().value(0))?;
+            }
+            None => self.append_null(),
+        }
+        Ok(())
+    }
+}
+
+/// Dictionary builder for the value types arrow has no dictionary builder for (string and binary views),
+/// or whose parameters arrow's builder drops (decimals). The distinct values are appended to a builder
+/// of the value type created by `make_builder`.
+struct ValuesDictionaryBuilder<K: ArrowDictionaryKeyType> {
+    data_type: DataType,
+    keys: PrimitiveBuilder<K>,
+    values: Box<dyn AppendableBuilder>,
+    /// position of every distinct value in `values`
+    positions: HashMap<ScalarValue, usize>,
+}
+
+impl<K: ArrowDictionaryKeyType> ValuesDictionaryBuilder<K> {
+    fn new(value_type: &DataType, capacity: usize) -> Result<Self> {
+        Ok(Self {
+            data_type: DataType::Dictionary(Box::new(K::DATA_TYPE), Box::new(value_type.clone())),
+            keys: PrimitiveBuilder::with_capacity(capacity),
+            values: make_builder(value_type, capacity)?,
+            positions: HashMap::new(),
+        })
+    }
+
+    fn build(keys: PrimitiveArray<K>, values: ArrayRef) -> ArrayRef {
+        let dictionary = DictionaryArray::<K>::try_new(keys, values)
+            .expect("the keys are positions of the appended values");
+        Arc::new(dictionary)
+    }
+}
+
+impl<K: ArrowDictionaryKeyType> ArrayBuilder for ValuesDictionaryBuilder<K> {
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        self.positions.clear();
+        Self::build(self.keys.finish(), self.values.finish())
+    }
+
+    fn finish_cloned(&self) -> ArrayRef {
+        Self::build(self.keys.finish_cloned(), self.values.finish_cloned())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn into_box_any(self: Box<Self>) -> Box<dyn std::any::Any> {
+        self
+    }
+}
+
+impl<K: ArrowDictionaryKeyType> AppendableBuilder for ValuesDictionaryBuilder<K> {
+    fn data_type(&self) -> DataType {
+        self.data_type.clone()
+    }
+
+    fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
+        let Some(array) = dictionary_value(value, &self.data_type)? else {
+            self.keys.append_null();
+            return Ok(());
+        };
+        let value = scalar_from_array(&array, 0)?;
+        let known = self.positions.get(&value).copied();
+        let position = known.unwrap_or(self.values.len());
+        // the key is checked before a new value is appended, so a full dictionary stays consistent
+        let key = K::Native::from_usize(position)
+            .ok_or(datafusion::arrow::error::ArrowError::DictionaryKeyOverflowError)?;
+        if known.is_none() {
+            self.values.append_scalar_value(value.clone())?;
+            self.positions.insert(value, position);
+        }
+        self.keys.append_value(key);
+        Ok(())
+    }
+}
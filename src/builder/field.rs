@@ -0,0 +1,201 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Array, ArrayBuilder, ArrayRef, AsArray, OffsetSizeTrait};
+use datafusion::arrow::buffer::{BooleanBuffer, NullBuffer, OffsetBuffer};
+use datafusion::arrow::compute::CastOptions;
+use datafusion::arrow::datatypes::{DataType, Field, FieldRef};
+use datafusion::error::Result;
+use datafusion::scalar::ScalarValue;
+
+use super::{cast_scalar, make_builder, AppendableBuilder};
+
+/// Returns a builder for the column of `field`. Unlike `make_builder` it keeps the whole field:
+/// nulls are rejected if the field or one of its nested fields is not nullable (a null child of a valid struct,
+/// a null list element or map value), and `field()` returns it with its metadata
+/// (e.g. the `ARROW:extension:name` of an extension type) to go with the finished array into a schema.
+/// The metadata of the nested fields is part of the data type, so the finished array keeps it.
+pub fn make_builder_for_field(field: &Field, capacity: usize) -> Result<FieldBuilder> {
+    Ok(FieldBuilder {
+        field: field.clone().into(),
+        builder: make_builder(field.data_type(), capacity)?,
+    })
+}
+
+/// Builder created by `make_builder_for_field`
+pub struct FieldBuilder {
+    field: FieldRef,
+    builder: Box<dyn AppendableBuilder>,
+}
+
+impl FieldBuilder {
+    /// The field of the built column, including its metadata
+    pub fn field(&self) -> &FieldRef {
+        &self.field
+    }
+
+    fn check_nulls(&self, nulls: bool) -> Result<()> {
+        if nulls && !self.field.is_nullable() {
+            return Err(null_error(&self.field));
+        }
+        Ok(())
+    }
+
+    /// checks the nested fields of a not null value, arrow would panic on their nulls when the array is finished
+    fn check_nested_nulls(&self, value: &ScalarValue) -> Result<()> {
+        if value.is_null() || !self.field.data_type().is_nested() {
+            return Ok(());
+        }
+        check_array_nulls(&self.field, &value.to_array()?, None)
+    }
+}
+
+fn null_error(field: &Field) -> datafusion::error::DataFusionError {
+    datafusion::error::DataFusionError::Execution(format!(
+        "Cannot append a null to the non-nullable field {}",
+        field.name()
+    ))
+}
+
+/// Returns an error if `array` or its nested arrays have a null where `field` doesn't allow it.
+/// Only the rows valid in `mask` are checked: children of a null struct or list don't count.
+fn check_array_nulls(field: &Field, array: &ArrayRef, mask: Option<&NullBuffer>) -> Result<()> {
+    let nulls = array.logical_nulls();
+    if !field.is_nullable() {
+        if let Some(nulls) = &nulls {
+            let unmasked = nulls
+                .iter()
+                .enumerate()
+                .any(|(i, valid)| !valid && mask.map_or(true, |mask| mask.is_valid(i)));
+            if unmasked {
+                return Err(null_error(field));
+            }
+        }
+    }
+
+    // the rows whose children have to be checked
+    let valid = NullBuffer::union(mask, nulls.as_ref());
+    // arrays of another type are left to the builder, which reports the mismatch
+    match field.data_type() {
+        DataType::Struct(fields) => {
+            if let Some(array) = array.as_struct_opt() {
+                for (field, column) in fields.iter().zip(array.columns()) {
+                    check_array_nulls(field, column, valid.as_ref())?;
+                }
+            }
+        }
+        DataType::List(item) => {
+            if let Some(list) = array.as_list_opt::<i32>() {
+                let mask = child_mask(list.offsets(), valid.as_ref(), list.values().len());
+                check_array_nulls(item, list.values(), mask.as_ref())?;
+            }
+        }
+        DataType::LargeList(item) => {
+            if let Some(list) = array.as_list_opt::<i64>() {
+                let mask = child_mask(list.offsets(), valid.as_ref(), list.values().len());
+                check_array_nulls(item, list.values(), mask.as_ref())?;
+            }
+        }
+        DataType::FixedSizeList(item, _) => {
+            if let Some(list) = array.as_fixed_size_list_opt() {
+                let size = list.value_length() as usize;
+                let mask = valid.as_ref().map(|valid| {
+                    let elements = (0..list.values().len()).map(|i| valid.is_valid(i / size));
+                    NullBuffer::new(BooleanBuffer::from_iter(elements))
+                });
+                check_array_nulls(item, list.values(), mask.as_ref())?;
+            }
+        }
+        DataType::Map(entries, _) => {
+            if let Some(map) = array.as_map_opt() {
+                let entries_array = Arc::new(map.entries().clone()) as ArrayRef;
+                let mask = child_mask(map.offsets(), valid.as_ref(), entries_array.len());
+                check_array_nulls(entries, &entries_array, mask.as_ref())?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Mask of the elements of a list-like array, elements of the rows not valid in `valid` are masked
+fn child_mask<O: OffsetSizeTrait>(
+    offsets: &OffsetBuffer<O>,
+    valid: Option<&NullBuffer>,
+    len: usize,
+) -> Option<NullBuffer> {
+    let valid = valid?;
+    let mut elements = vec![false; len];
+    for (row, range) in offsets.windows(2).enumerate() {
+        if valid.is_valid(row) {
+            elements[range[0].as_usize()..range[1].as_usize()].fill(true);
+        }
+    }
+    Some(NullBuffer::from(elements))
+}
+
+impl ArrayBuilder for FieldBuilder {
+    fn len(&self) -> usize {
+        self.builder.len()
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        self.builder.finish()
+    }
+
+    fn finish_cloned(&self) -> ArrayRef {
+        self.builder.finish_cloned()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn into_box_any(self: Box<Self>) -> Box<dyn std::any::Any> {
+        self
+    }
+}
+
+impl AppendableBuilder for FieldBuilder {
+    fn data_type(&self) -> DataType {
+        self.field.data_type().clone()
+    }
+
+    fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
+        self.check_nulls(value.is_null())?;
+        self.check_nested_nulls(&value)?;
+        self.builder.append_scalar_value(value)
+    }
+
+    fn append_scalar_value_cast(
+        &mut self,
+        value: ScalarValue,
+        options: &CastOptions,
+    ) -> Result<()> {
+        // a safe cast can turn a value into a null
+        let value = cast_scalar(value, self.field.data_type(), options)?;
+        self.append_scalar_value(value)
+    }
+
+    fn append_scalar_n(&mut self, value: ScalarValue, n: usize) -> Result<()> {
+        self.check_nulls(n > 0 && value.is_null())?;
+        self.check_nested_nulls(&value)?;
+        self.builder.append_scalar_n(value, n)
+    }
+
+    fn append_nulls(&mut self, n: usize) -> Result<()> {
+        self.check_nulls(n > 0)?;
+        self.builder.append_nulls(n)
+    }
+
+    fn append_array_slice(&mut self, array: &ArrayRef, offset: usize, len: usize) -> Result<()> {
+        if !self.field.is_nullable() || self.field.data_type().is_nested() {
+            super::check_slice(array.as_ref(), offset, len)?;
+            check_array_nulls(&self.field, &array.slice(offset, len), None)?;
+        }
+        self.builder.append_array_slice(array, offset, len)
+    }
+}
@@ -1,26 +1,90 @@
 mod builder_macros;
+mod bulk;
+mod dictionary;
+mod field;
+mod run_end;
+mod typed;
+mod union;
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use datafusion::arrow::array::{
+    Array, ArrayRef, AsArray, GenericListBuilder, MapArray, OffsetSizeTrait,
+};
 use datafusion::arrow::array::{
-    BinaryBuilder, BooleanBuilder, Date32Builder, Date64Builder, Decimal128Builder,
-    Decimal256Builder, DurationMicrosecondBuilder, DurationMillisecondBuilder,
+    BinaryBuilder, BinaryViewBuilder, BooleanBuilder, Date32Builder, Date64Builder,
+    Decimal128Builder, Decimal256Builder, DurationMicrosecondBuilder, DurationMillisecondBuilder,
     DurationNanosecondBuilder, DurationSecondBuilder, FixedSizeBinaryBuilder, FixedSizeListBuilder,
     Float16Builder, Float32Builder, Float64Builder, Int16Builder, Int32Builder, Int64Builder,
     Int8Builder, IntervalDayTimeBuilder, IntervalMonthDayNanoBuilder, IntervalYearMonthBuilder,
-    LargeBinaryBuilder, LargeListBuilder, LargeStringBuilder, ListBuilder, NullBuilder,
-    StringBuilder, Time32MillisecondBuilder, Time32SecondBuilder, Time64MicrosecondBuilder,
+    LargeBinaryBuilder, LargeListBuilder, LargeStringBuilder, ListBuilder, MapBuilder,
+    MapFieldNames, NullBuilder, StringBuilder, StringViewBuilder, StructBuilder,
+    Time32MillisecondBuilder, Time32SecondBuilder, Time64MicrosecondBuilder,
     Time64NanosecondBuilder, TimestampMicrosecondBuilder, TimestampMillisecondBuilder,
     TimestampNanosecondBuilder, TimestampSecondBuilder, UInt16Builder, UInt32Builder,
     UInt64Builder, UInt8Builder,
 };
-use datafusion::arrow::datatypes::{DataType, IntervalUnit, TimeUnit};
+use datafusion::arrow::compute::{cast_with_options, CastOptions};
+use datafusion::arrow::datatypes::{
+    DataType, Decimal128Type, Decimal256Type, DecimalType, IntervalUnit, TimeUnit,
+};
 use datafusion::error::Result;
 use datafusion::{arrow::array::ArrayBuilder, scalar::ScalarValue};
 
 use crate::impl_builder_append;
+use crate::ordering::compare_scalars;
+use bulk::{check_slice, BulkAppend};
+use dictionary::make_dictionary_builder;
+pub use field::{make_builder_for_field, FieldBuilder};
+pub use run_end::RunEndEncodedBuilder;
+use typed::type_mismatch;
+pub use typed::TypeMismatch;
+pub use typed::TypedBuilder;
+pub use union::UnionArrayBuilder;
 
 /// All builders implement append function, but the general Builder trait doesn't have it.
 /// Because we need to abstract over the different builders, I extended ArrayBuilder with this method.
+/// The bulk methods have one value at a time defaults, builders override them with copies of whole ranges.
 pub trait AppendableBuilder: ArrayBuilder {
+    /// Data type of the built array. arrow builders that don't report their type are wrapped in a `TypedBuilder`,
+    /// so this never builds the array to read it.
+    fn data_type(&self) -> DataType;
+
+    /// Appends `value`, a value of another type than `data_type()` is a `TypeMismatch` error
     fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()>;
+
+    /// Appends `value` cast to `data_type()` with the arrow cast rules.
+    /// With `safe` options a value that doesn't fit (e.g. an overflow) is appended as a null, otherwise it is an error.
+    fn append_scalar_value_cast(
+        &mut self,
+        value: ScalarValue,
+        options: &CastOptions,
+    ) -> Result<()> {
+        let value = cast_scalar(value, &self.data_type(), options)?;
+        self.append_scalar_value(value)
+    }
+
+    /// Appends `value` `n` times
+    fn append_scalar_n(&mut self, value: ScalarValue, n: usize) -> Result<()> {
+        for _ in 0..n {
+            self.append_scalar_value(value.clone())?;
+        }
+        Ok(())
+    }
+
+    /// Appends `n` nulls
+    fn append_nulls(&mut self, n: usize) -> Result<()> {
+        self.append_scalar_n(ScalarValue::Null, n)
+    }
+
+    /// Appends `len` values of `array` starting at `offset`
+    fn append_array_slice(&mut self, array: &ArrayRef, offset: usize, len: usize) -> Result<()> {
+        check_slice(array.as_ref(), offset, len)?;
+        for i in offset..offset + len {
+            self.append_scalar_value(scalar_from_array(array, i)?)?;
+        }
+        Ok(())
+    }
 }
 
 /// Returns a appendable builder with capacity `capacity` that corresponds to the datatype `DataType`
@@ -46,18 +110,23 @@ pub fn make_builder(datatype: &DataType, capacity: usize) -> Result<Box<dyn Appe
         DataType::Float64 => Box::new(Float64Builder::with_capacity(capacity)),
         DataType::Binary => Box::new(BinaryBuilder::with_capacity(capacity, 1024)),
         DataType::LargeBinary => Box::new(LargeBinaryBuilder::with_capacity(capacity, 1024)),
-        DataType::FixedSizeBinary(len) => {
-            Box::new(FixedSizeBinaryBuilder::with_capacity(capacity, *len))
-        }
+        DataType::FixedSizeBinary(len) => typed(
+            datatype,
+            FixedSizeBinaryBuilder::with_capacity(capacity, *len),
+        ),
 
-        DataType::Decimal128(p, s) => Box::new(
-            Decimal128Builder::with_capacity(capacity).with_data_type(DataType::Decimal128(*p, *s)),
+        DataType::Decimal128(_, _) => typed(
+            datatype,
+            Decimal128Builder::with_capacity(capacity).with_data_type(datatype.clone()),
         ),
-        DataType::Decimal256(p, s) => Box::new(
-            Decimal256Builder::with_capacity(capacity).with_data_type(DataType::Decimal256(*p, *s)),
+        DataType::Decimal256(_, _) => typed(
+            datatype,
+            Decimal256Builder::with_capacity(capacity).with_data_type(datatype.clone()),
         ),
         DataType::Utf8 => Box::new(StringBuilder::with_capacity(capacity, 1024)),
         DataType::LargeUtf8 => Box::new(LargeStringBuilder::with_capacity(capacity, 1024)),
+        DataType::Utf8View => Box::new(StringViewBuilder::with_capacity(capacity)),
+        DataType::BinaryView => Box::new(BinaryViewBuilder::with_capacity(capacity)),
         DataType::Date32 => Box::new(Date32Builder::with_capacity(capacity)),
         DataType::Date64 => Box::new(Date64Builder::with_capacity(capacity)),
         DataType::Time32(TimeUnit::Second) => {
@@ -72,21 +141,21 @@ pub fn make_builder(datatype: &DataType, capacity: usize) -> Result<Box<dyn Appe
         DataType::Time64(TimeUnit::Nanosecond) => {
             Box::new(Time64NanosecondBuilder::with_capacity(capacity))
         }
-        DataType::Timestamp(TimeUnit::Second, tz) => Box::new(
-            TimestampSecondBuilder::with_capacity(capacity)
-                .with_data_type(DataType::Timestamp(TimeUnit::Second, tz.clone())),
+        DataType::Timestamp(TimeUnit::Second, _) => typed(
+            datatype,
+            TimestampSecondBuilder::with_capacity(capacity).with_data_type(datatype.clone()),
         ),
-        DataType::Timestamp(TimeUnit::Millisecond, tz) => Box::new(
-            TimestampMillisecondBuilder::with_capacity(capacity)
-                .with_data_type(DataType::Timestamp(TimeUnit::Millisecond, tz.clone())),
+        DataType::Timestamp(TimeUnit::Millisecond, _) => typed(
+            datatype,
+            TimestampMillisecondBuilder::with_capacity(capacity).with_data_type(datatype.clone()),
         ),
-        DataType::Timestamp(TimeUnit::Microsecond, tz) => Box::new(
-            TimestampMicrosecondBuilder::with_capacity(capacity)
-                .with_data_type(DataType::Timestamp(TimeUnit::Microsecond, tz.clone())),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => typed(
+            datatype,
+            TimestampMicrosecondBuilder::with_capacity(capacity).with_data_type(datatype.clone()),
         ),
-        DataType::Timestamp(TimeUnit::Nanosecond, tz) => Box::new(
-            TimestampNanosecondBuilder::with_capacity(capacity)
-                .with_data_type(DataType::Timestamp(TimeUnit::Nanosecond, tz.clone())),
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => typed(
+            datatype,
+            TimestampNanosecondBuilder::with_capacity(capacity).with_data_type(datatype.clone()),
         ),
         DataType::Interval(IntervalUnit::YearMonth) => {
             Box::new(IntervalYearMonthBuilder::with_capacity(capacity))
@@ -111,19 +180,74 @@ pub fn make_builder(datatype: &DataType, capacity: usize) -> Result<Box<dyn Appe
         }
         DataType::List(field) => {
             let builder = make_builder(field.data_type(), capacity)?;
-            Box::new(ListBuilder::with_capacity(builder, capacity).with_field(field.clone()))
+            typed(
+                datatype,
+                ListBuilder::with_capacity(builder, capacity).with_field(field.clone()),
+            )
         }
         DataType::LargeList(field) => {
             let builder = make_builder(field.data_type(), capacity)?;
-            Box::new(LargeListBuilder::with_capacity(builder, capacity).with_field(field.clone()))
+            typed(
+                datatype,
+                LargeListBuilder::with_capacity(builder, capacity).with_field(field.clone()),
+            )
         }
         DataType::FixedSizeList(field, len) => {
             let builder = make_builder(field.data_type(), capacity)?;
-            Box::new(
+            typed(
+                datatype,
                 FixedSizeListBuilder::with_capacity(builder, *len, capacity)
                     .with_field(field.clone()),
             )
         }
+        DataType::Struct(fields) => {
+            let builders = fields
+                .iter()
+                .map(|field| {
+                    let builder = StructFieldBuilder::new(field.data_type(), capacity)?;
+                    Ok(Box::new(builder) as Box<dyn ArrayBuilder>)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            typed(datatype, StructBuilder::new(fields.clone(), builders))
+        }
+        DataType::Map(field, sorted) => {
+            let DataType::Struct(entries) = field.data_type() else {
+                return Err(datafusion::error::DataFusionError::Execution(format!(
+                    "Map entries must be a struct, got {:?}",
+                    field.data_type()
+                )));
+            };
+            if entries.len() != 2 {
+                return Err(datafusion::error::DataFusionError::Execution(format!(
+                    "Map entries must have a key and a value field, got {} fields",
+                    entries.len()
+                )));
+            }
+            let names = MapFieldNames {
+                entry: field.name().clone(),
+                key: entries[0].name().clone(),
+                value: entries[1].name().clone(),
+            };
+            let keys = make_builder(entries[0].data_type(), capacity)?;
+            let values = make_builder(entries[1].data_type(), capacity)?;
+            let builder = MapBuilder::with_capacity(Some(names), keys, values, capacity)
+                .with_values_field(entries[1].clone());
+            if *sorted {
+                typed(datatype, SortedMapBuilder::new(builder))
+            } else {
+                typed(datatype, builder)
+            }
+        }
+        DataType::Dictionary(key_type, value_type) => {
+            make_dictionary_builder(key_type, value_type, capacity)?
+        }
+        DataType::Union(fields, mode) => Box::new(UnionArrayBuilder::new(fields, *mode, capacity)?),
+        DataType::RunEndEncoded(_, _) => Box::new(RunEndEncodedBuilder::new(datatype, capacity)?),
+        DataType::ListView(_) | DataType::LargeListView(_) => {
+            return Err(datafusion::error::DataFusionError::NotImplemented(format!(
+                "Data type {datatype:?} has no array implementation in arrow yet"
+            )))
+        }
 
         t => {
             return Err(datafusion::error::DataFusionError::Execution(format!(
@@ -133,165 +257,311 @@ pub fn make_builder(datatype: &DataType, capacity: usize) -> Result<Box<dyn Appe
     })
 }
 
+fn typed<B: ArrayBuilder>(data_type: &DataType, builder: B) -> Box<dyn AppendableBuilder>
+where
+    TypedBuilder<B>: AppendableBuilder,
+{
+    Box::new(TypedBuilder::new(data_type.clone(), builder))
+}
+
+/// Casts `value` to `data_type`, DataFusion's `ScalarValue::cast_to` has no safe option
+pub(crate) fn cast_scalar(
+    value: ScalarValue,
+    data_type: &DataType,
+    options: &CastOptions,
+) -> Result<ScalarValue> {
+    let value_type = value.data_type();
+    if value_type == DataType::Null || &value_type == data_type {
+        return Ok(value);
+    }
+    let array = cast_with_options(&value.to_array()?, data_type, options)?;
+    // arrow doesn't check the precision when the scale stays the same
+    let value = scalar_from_array(&array, 0)?;
+    let fits = match &value {
+        ScalarValue::Decimal128(Some(v), p, _) => {
+            Decimal128Type::validate_decimal_precision(*v, *p)
+        }
+        ScalarValue::Decimal256(Some(v), p, _) => {
+            Decimal256Type::validate_decimal_precision(*v, *p)
+        }
+        _ => Ok(()),
+    };
+    match fits {
+        Ok(()) => Ok(value),
+        Err(_) if options.safe => ScalarValue::try_from(data_type),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// `ScalarValue::try_from_array` that also reads union and run-end encoded arrays,
+/// which DataFusion cannot turn into scalars.
+/// A run-end encoded value is read as the scalar of the value type, as `RunEndEncodedBuilder` appends it.
+pub(crate) fn scalar_from_array(array: &dyn Array, index: usize) -> Result<ScalarValue> {
+    match array.data_type() {
+        DataType::Union(_, _) => union::union_value(array, index),
+        DataType::RunEndEncoded(_, _) => run_end::run_end_value(array, index),
+        _ => ScalarValue::try_from_array(array, index),
+    }
+}
+
 impl AppendableBuilder for NullBuilder {
+    fn data_type(&self) -> DataType {
+        DataType::Null
+    }
+
     fn append_scalar_value(&mut self, _: ScalarValue) -> Result<()> {
         self.append_null();
         Ok(())
     }
+
+    fn append_scalar_n(&mut self, _: ScalarValue, n: usize) -> Result<()> {
+        NullBuilder::append_nulls(self, n);
+        Ok(())
+    }
+
+    fn append_nulls(&mut self, n: usize) -> Result<()> {
+        NullBuilder::append_nulls(self, n);
+        Ok(())
+    }
+
+    fn append_array_slice(&mut self, array: &ArrayRef, offset: usize, len: usize) -> Result<()> {
+        check_slice(array.as_ref(), offset, len)?;
+        NullBuilder::append_nulls(self, len);
+        Ok(())
+    }
 }
 
-impl AppendableBuilder for FixedSizeBinaryBuilder {
+impl AppendableBuilder for TypedBuilder<FixedSizeBinaryBuilder> {
+    fn data_type(&self) -> DataType {
+        self.data_type.clone()
+    }
+
     fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
+        self.check(&value.data_type())?;
         match value {
             ScalarValue::FixedSizeBinary(_bin, Some(value)) => {
-                self.append_value(value)?;
+                self.builder.append_value(value)?;
             }
-            ScalarValue::FixedSizeBinary(_bin, None) => {
-                self.append_null();
-            }
-            _ => {
-                return Err(datafusion::error::DataFusionError::Internal(
-                    "Invalid scalar value for FixedSizeBinaryBuilder".to_string(),
-                ));
+            ScalarValue::FixedSizeBinary(_, None) | ScalarValue::Null => {
+                self.builder.append_null();
             }
+            value => return Err(type_mismatch(self.data_type(), &value)),
         }
         Ok(())
     }
+
+    fn append_nulls(&mut self, n: usize) -> Result<()> {
+        self.bulk_append_nulls(n);
+        Ok(())
+    }
+
+    fn append_array_slice(&mut self, array: &ArrayRef, offset: usize, len: usize) -> Result<()> {
+        check_slice(array.as_ref(), offset, len)?;
+        self.check(array.data_type())?;
+        self.bulk_append_slice(array.as_ref(), offset, len)
+    }
 }
 
-impl AppendableBuilder for Decimal128Builder {
-    fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
-        match value {
-            ScalarValue::Decimal128(Some(value), _p, _s) => {
-                self.append_value(value);
-            }
-            ScalarValue::Decimal128(None, _p, _s) => {
-                self.append_null();
-            }
-            _ => {
-                return Err(datafusion::error::DataFusionError::Internal(
-                    "Invalid scalar value for Decimal128Builder".to_string(),
-                ));
-            }
+/// Appends the elements of a single row list array to the `values` builder.
+/// Returns false if the list is null, so no elements are appended.
+/// The list type is checked against `data_type` first, so a null or empty list of another type is rejected too.
+fn append_list_values<T: AppendableBuilder>(
+    data_type: &DataType,
+    list: &dyn Array,
+    values: &mut T,
+) -> Result<bool> {
+    if list.len() != 1 {
+        return Err(datafusion::error::DataFusionError::Internal(format!(
+            "List scalar value must have exactly one row, got {}",
+            list.len()
+        )));
+    }
+    typed::check_type(data_type, list.data_type())?;
+    if list.is_null(0) {
+        return Ok(false);
+    }
+
+    let list = match list.data_type() {
+        DataType::List(_) => list.as_list::<i32>().value(0),
+        DataType::LargeList(_) => list.as_list::<i64>().value(0),
+        DataType::FixedSizeList(_, _) => list.as_fixed_size_list().value(0),
+        t => {
+            return Err(datafusion::error::DataFusionError::Internal(format!(
+                "Data type {t:?} is not a list"
+            )))
         }
-        Ok(())
+    };
+    for i in 0..list.len() {
+        let value = scalar_from_array(&list, i)?;
+        values.append_scalar_value(value)?;
     }
+
+    Ok(true)
 }
 
-impl AppendableBuilder for Decimal256Builder {
-    fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
-        match value {
-            ScalarValue::Decimal256(Some(value), _p, _s) => {
-                self.append_value(value);
-            }
-            ScalarValue::Decimal256(None, _p, _s) => {
-                self.append_null();
-            }
-            _ => {
-                return Err(datafusion::error::DataFusionError::Internal(
-                    "Invalid scalar value for Decimal256Builder".to_string(),
-                ));
-            }
+/// Copies the rows `offset..offset + len` of a list array, the elements of every row are appended as one slice
+fn append_list_slice<O: OffsetSizeTrait, T: AppendableBuilder>(
+    typed: &mut TypedBuilder<GenericListBuilder<O, T>>,
+    array: &ArrayRef,
+    offset: usize,
+    len: usize,
+) -> Result<()> {
+    check_slice(array.as_ref(), offset, len)?;
+    let list = array
+        .as_list_opt::<O>()
+        .ok_or_else(|| typed::array_type_mismatch(typed.data_type.clone(), array.as_ref()))?;
+    typed::check_type(&typed.data_type, array.data_type())?;
+    let builder = &mut typed.builder;
+    let offsets = list.value_offsets();
+    for i in offset..offset + len {
+        if list.is_valid(i) {
+            let start = offsets[i].as_usize();
+            let elements = offsets[i + 1].as_usize() - start;
+            builder
+                .values()
+                .append_array_slice(list.values(), start, elements)?;
         }
-        Ok(())
+        builder.append(list.is_valid(i));
     }
+    Ok(())
 }
 
-impl<T: AppendableBuilder> AppendableBuilder for ListBuilder<T> {
+impl<T: AppendableBuilder> AppendableBuilder for TypedBuilder<ListBuilder<T>> {
+    fn data_type(&self) -> DataType {
+        self.data_type.clone()
+    }
+
     fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
         match value {
             ScalarValue::List(list) => {
-                //TODO: assert that the array have exectlly one value
-                let list = list.value(0);
-                let len = list.len();
-
-                for i in 0..len {
-                    let value = ScalarValue::try_from_array(&list, i)?;
-                    self.values().append_scalar_value(value)?;
-                }
-
-                self.append(true);
-            }
-
-            _ => {
-                return Err(datafusion::error::DataFusionError::Internal(
-                    "Invalid scalar value for ListBuilder".to_string(),
-                ));
+                let is_valid =
+                    append_list_values(&self.data_type, list.as_ref(), self.builder.values())?;
+                self.builder.append(is_valid);
             }
+            ScalarValue::Null => self.builder.append_null(),
+            value => return Err(type_mismatch(self.data_type(), &value)),
         }
         Ok(())
     }
+
+    fn append_array_slice(&mut self, array: &ArrayRef, offset: usize, len: usize) -> Result<()> {
+        append_list_slice(self, array, offset, len)
+    }
 }
 
-impl<T: AppendableBuilder> AppendableBuilder for FixedSizeListBuilder<T> {
+impl<T: AppendableBuilder> AppendableBuilder for TypedBuilder<FixedSizeListBuilder<T>> {
+    fn data_type(&self) -> DataType {
+        self.data_type.clone()
+    }
+
     fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
+        let data_type = self.data_type.clone();
+        let builder = &mut self.builder;
+        // a null list still occupies `value_length` child slots
+        let null_values =
+            |builder: &mut FixedSizeListBuilder<T>, value_type: Option<&DataType>| -> Result<()> {
+                let null = match value_type {
+                    Some(value_type) => ScalarValue::try_from(value_type)?,
+                    None => ScalarValue::Null,
+                };
+                for _ in 0..builder.value_length() {
+                    builder.values().append_scalar_value(null.clone())?;
+                }
+                builder.append(false);
+                Ok(())
+            };
+
         match value {
             ScalarValue::FixedSizeList(list) => {
-                // TODO: assert that the array have exectlly one value
-                let list = list.value(0);
-                let len = list.len();
-
-                for i in 0..len {
-                    let value = ScalarValue::try_from_array(&list, i)?;
-                    self.values().append_scalar_value(value)?;
+                if append_list_values(&data_type, list.as_ref(), builder.values())? {
+                    builder.append(true);
+                } else {
+                    null_values(builder, Some(&list.value_type()))?;
                 }
-
-                self.append(true);
             }
+            ScalarValue::Null => null_values(builder, None)?,
+            value => return Err(type_mismatch(data_type, &value)),
+        }
+        Ok(())
+    }
 
-            _ => {
-                return Err(datafusion::error::DataFusionError::Internal(
-                    "Invalid scalar value for FixedSizeListBuilder".to_string(),
-                ));
-            }
+    fn append_array_slice(&mut self, array: &ArrayRef, offset: usize, len: usize) -> Result<()> {
+        check_slice(array.as_ref(), offset, len)?;
+        let list = array
+            .as_fixed_size_list_opt()
+            .ok_or_else(|| typed::array_type_mismatch(self.data_type(), array.as_ref()))?;
+        typed::check_type(&self.data_type, array.data_type())?;
+        // null lists occupy their slots in the child too, so all elements are one range
+        let start = list.value_offset(offset) as usize;
+        let size = list.value_length() as usize;
+        self.builder
+            .values()
+            .append_array_slice(list.values(), start, len * size)?;
+        for i in offset..offset + len {
+            self.builder.append(list.is_valid(i));
         }
         Ok(())
     }
 }
 
-impl<T: AppendableBuilder> AppendableBuilder for LargeListBuilder<T> {
+impl<T: AppendableBuilder> AppendableBuilder for TypedBuilder<LargeListBuilder<T>> {
+    fn data_type(&self) -> DataType {
+        self.data_type.clone()
+    }
+
     fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
         match value {
             ScalarValue::LargeList(list) => {
-                let list = list.value(0);
-                let len = list.len();
-
-                for i in 0..len {
-                    let value = ScalarValue::try_from_array(&list, i)?;
-                    self.values().append_scalar_value(value)?;
-                }
-
-                self.append(true);
-            }
-            _ => {
-                return Err(datafusion::error::DataFusionError::Internal(
-                    "Invalid scalar value for LargeListBuilder".to_string(),
-                ));
+                let is_valid =
+                    append_list_values(&self.data_type, list.as_ref(), self.builder.values())?;
+                self.builder.append(is_valid);
             }
+            ScalarValue::Null => self.builder.append_null(),
+            value => return Err(type_mismatch(self.data_type(), &value)),
         }
         Ok(())
     }
+
+    fn append_array_slice(&mut self, array: &ArrayRef, offset: usize, len: usize) -> Result<()> {
+        append_list_slice(self, array, offset, len)
+    }
 }
 
-impl ArrayBuilder for Box<dyn AppendableBuilder> {
+/// StructBuilder hands out its children only through `field_builder::<T>()` downcasts,
+/// so the children created by `make_builder` are wrapped in this type to be reachable as `AppendableBuilder`.
+pub struct StructFieldBuilder {
+    data_type: DataType,
+    builder: Box<dyn AppendableBuilder>,
+}
+
+impl StructFieldBuilder {
+    pub fn new(data_type: &DataType, capacity: usize) -> Result<Self> {
+        Ok(Self {
+            data_type: data_type.clone(),
+            builder: make_builder(data_type, capacity)?,
+        })
+    }
+}
+
+impl ArrayBuilder for StructFieldBuilder {
     fn len(&self) -> usize {
-        self.as_ref().len()
+        self.builder.len()
     }
 
-    fn finish(&mut self) -> datafusion::arrow::array::ArrayRef {
-        self.as_mut().finish()
+    fn finish(&mut self) -> ArrayRef {
+        self.builder.finish()
     }
 
-    fn finish_cloned(&self) -> datafusion::arrow::array::ArrayRef {
-        self.as_ref().finish_cloned()
+    fn finish_cloned(&self) -> ArrayRef {
+        self.builder.finish_cloned()
     }
 
     fn as_any(&self) -> &dyn std::any::Any {
-        self.as_ref().as_any()
+        self
     }
 
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
-        self.as_mut().as_any_mut()
+        self
     }
 
     fn into_box_any(self: Box<Self>) -> Box<dyn std::any::Any> {
@@ -299,90 +569,353 @@ impl ArrayBuilder for Box<dyn AppendableBuilder> {
     }
 }
 
-impl AppendableBuilder for Box<dyn AppendableBuilder> {
+impl AppendableBuilder for StructFieldBuilder {
+    fn data_type(&self) -> DataType {
+        self.data_type.clone()
+    }
+
     fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
-        self.as_mut().append_scalar_value(value)
+        self.builder.append_scalar_value(value)
+    }
+
+    fn append_scalar_value_cast(
+        &mut self,
+        value: ScalarValue,
+        options: &CastOptions,
+    ) -> Result<()> {
+        self.builder.append_scalar_value_cast(value, options)
+    }
+
+    fn append_scalar_n(&mut self, value: ScalarValue, n: usize) -> Result<()> {
+        self.builder.append_scalar_n(value, n)
+    }
+
+    fn append_nulls(&mut self, n: usize) -> Result<()> {
+        self.builder.append_nulls(n)
+    }
+
+    fn append_array_slice(&mut self, array: &ArrayRef, offset: usize, len: usize) -> Result<()> {
+        self.builder.append_array_slice(array, offset, len)
     }
 }
 
-impl AppendableBuilder for TimestampMicrosecondBuilder {
+impl AppendableBuilder for TypedBuilder<StructBuilder> {
+    fn data_type(&self) -> DataType {
+        self.data_type.clone()
+    }
+
     fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
-        match value {
-            ScalarValue::TimestampMicrosecond(Some(value), _) => {
-                self.append_value(value);
-            }
-            ScalarValue::TimestampMicrosecond(None, _) => {
-                self.append_null();
-            }
-            _ => {
-                return Err(datafusion::error::DataFusionError::Internal(
-                    "Invalid scalar value for TimestampMicrosecondBuilder".to_string(),
-                ));
-            }
+        let fields = match &value {
+            ScalarValue::Struct(array) if !array.is_null(0) => Some(array.columns()),
+            ScalarValue::Struct(_) | ScalarValue::Null => None,
+            value => return Err(type_mismatch(self.data_type(), value)),
+        };
+
+        // all values are checked before the first child grows, so a rejected struct leaves the children
+        // with the same length
+        let num_fields = self.builder.num_fields();
+        if fields.is_some_and(|fields| fields.len() != num_fields) {
+            return Err(type_mismatch(self.data_type(), &value));
+        }
+        let mut values = Vec::with_capacity(num_fields);
+        for i in 0..num_fields {
+            let child = self
+                .builder
+                .field_builder::<StructFieldBuilder>(i)
+                .ok_or_else(|| {
+                    datafusion::error::DataFusionError::Internal(
+                        "StructBuilder children must be created by make_builder".to_string(),
+                    )
+                })?;
+            // children of a null struct get a null of their own type
+            let value = match fields {
+                Some(fields) => scalar_from_array(&fields[i], 0)?,
+                None => ScalarValue::try_from(&child.data_type)?,
+            };
+            typed::check_type(&child.data_type, &value.data_type())?;
+            values.push(value);
+        }
+        for (i, value) in values.into_iter().enumerate() {
+            self.builder
+                .field_builder::<StructFieldBuilder>(i)
+                .expect("the children are checked above")
+                .append_scalar_value(value)?;
         }
+        self.builder.append(fields.is_some());
+
         Ok(())
     }
-}
 
-impl AppendableBuilder for TimestampMillisecondBuilder {
-    fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
-        match value {
-            ScalarValue::TimestampMillisecond(Some(value), _) => {
-                self.append_value(value);
-            }
-            ScalarValue::TimestampMillisecond(None, _) => {
-                self.append_null();
-            }
-            _ => {
-                return Err(datafusion::error::DataFusionError::Internal(
-                    "Invalid scalar value for TimestampMillisecondBuilder".to_string(),
-                ));
-            }
+    fn append_array_slice(&mut self, array: &ArrayRef, offset: usize, len: usize) -> Result<()> {
+        check_slice(array.as_ref(), offset, len)?;
+        let array = array
+            .as_struct_opt()
+            .ok_or_else(|| typed::array_type_mismatch(self.data_type(), array.as_ref()))?;
+        // checked before the first child is appended, so a rejected slice leaves the builder consistent
+        typed::check_type(&self.data_type, array.data_type())?;
+        for (i, column) in array.columns().iter().enumerate() {
+            let child = self
+                .builder
+                .field_builder::<StructFieldBuilder>(i)
+                .ok_or_else(|| {
+                    datafusion::error::DataFusionError::Internal(
+                        "StructBuilder children must be created by make_builder".to_string(),
+                    )
+                })?;
+            child.append_array_slice(column, offset, len)?;
+        }
+        for i in offset..offset + len {
+            self.builder.append(array.is_valid(i));
         }
         Ok(())
     }
 }
 
-impl AppendableBuilder for TimestampNanosecondBuilder {
+/// Appends the entries of a single row map array to the key and value builders of `builder`.
+/// Returns false if the map is null, so no entries are appended.
+/// With `sorted`, the keys must be in strictly ascending order.
+/// The entries are checked against `data_type` before the first key is appended, so a rejected map leaves
+/// the keys and values with the same length.
+fn append_map_entries<K: AppendableBuilder, V: AppendableBuilder>(
+    data_type: &DataType,
+    map: &MapArray,
+    builder: &mut MapBuilder<K, V>,
+    sorted: bool,
+) -> Result<bool> {
+    if map.len() != 1 {
+        return Err(datafusion::error::DataFusionError::Internal(format!(
+            "Map scalar value must have exactly one row, got {}",
+            map.len()
+        )));
+    }
+    typed::check_type(data_type, map.data_type())?;
+    if map.is_null(0) {
+        return Ok(false);
+    }
+
+    let entries = map.value(0);
+    let mut keys = Vec::with_capacity(entries.len());
+    for i in 0..entries.len() {
+        let key = scalar_from_array(entries.column(0), i)?;
+        if sorted
+            && keys
+                .last()
+                .is_some_and(|previous| compare_scalars(previous, &key) != Some(Ordering::Less))
+        {
+            return Err(datafusion::error::DataFusionError::Execution(
+                "Map keys must be sorted for a map type with sorted keys".to_string(),
+            ));
+        }
+        keys.push(key);
+    }
+    for (i, key) in keys.into_iter().enumerate() {
+        builder.keys().append_scalar_value(key)?;
+        builder
+            .values()
+            .append_scalar_value(scalar_from_array(entries.column(1), i)?)?;
+    }
+
+    Ok(true)
+}
+
+impl<K: AppendableBuilder, V: AppendableBuilder> AppendableBuilder
+    for TypedBuilder<MapBuilder<K, V>>
+{
+    fn data_type(&self) -> DataType {
+        self.data_type.clone()
+    }
+
     fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
         match value {
-            ScalarValue::TimestampNanosecond(Some(value), _) => {
-                self.append_value(value);
-            }
-            ScalarValue::TimestampNanosecond(None, _) => {
-                self.append_null();
+            ScalarValue::Map(map) => {
+                let is_valid =
+                    append_map_entries(&self.data_type, map.as_ref(), &mut self.builder, false)?;
+                self.builder.append(is_valid)?;
             }
-            _ => {
-                return Err(datafusion::error::DataFusionError::Internal(
-                    "Invalid scalar value for TimestampNanosecondBuilder".to_string(),
-                ));
+            ScalarValue::Null => self.builder.append(false)?,
+            value => return Err(type_mismatch(self.data_type(), &value)),
+        }
+        Ok(())
+    }
+
+    fn append_array_slice(&mut self, array: &ArrayRef, offset: usize, len: usize) -> Result<()> {
+        check_slice(array.as_ref(), offset, len)?;
+        let map = array
+            .as_map_opt()
+            .ok_or_else(|| typed::array_type_mismatch(self.data_type(), array.as_ref()))?;
+        typed::check_type(&self.data_type, array.data_type())?;
+        let offsets = map.value_offsets();
+        for i in offset..offset + len {
+            if map.is_valid(i) {
+                let start = offsets[i] as usize;
+                let entries = offsets[i + 1] as usize - start;
+                self.builder
+                    .keys()
+                    .append_array_slice(map.keys(), start, entries)?;
+                self.builder
+                    .values()
+                    .append_array_slice(map.values(), start, entries)?;
             }
+            self.builder.append(map.is_valid(i))?;
         }
         Ok(())
     }
 }
 
-impl AppendableBuilder for TimestampSecondBuilder {
+/// MapBuilder always finishes maps with unsorted keys, this wrapper builds maps with the `keys_sorted` flag
+/// and checks that the appended keys are sorted.
+pub struct SortedMapBuilder {
+    builder: MapBuilder<Box<dyn AppendableBuilder>, Box<dyn AppendableBuilder>>,
+}
+
+impl SortedMapBuilder {
+    pub fn new(
+        builder: MapBuilder<Box<dyn AppendableBuilder>, Box<dyn AppendableBuilder>>,
+    ) -> Self {
+        Self { builder }
+    }
+
+    fn sorted(map: MapArray) -> ArrayRef {
+        let (field, offsets, entries, nulls, _) = map.into_parts();
+        Arc::new(MapArray::new(field, offsets, entries, nulls, true))
+    }
+}
+
+impl ArrayBuilder for SortedMapBuilder {
+    fn len(&self) -> usize {
+        self.builder.len()
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        Self::sorted(self.builder.finish())
+    }
+
+    fn finish_cloned(&self) -> ArrayRef {
+        Self::sorted(self.builder.finish_cloned())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn into_box_any(self: Box<Self>) -> Box<dyn std::any::Any> {
+        self
+    }
+}
+
+impl AppendableBuilder for TypedBuilder<SortedMapBuilder> {
+    fn data_type(&self) -> DataType {
+        self.data_type.clone()
+    }
+
     fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
         match value {
-            ScalarValue::TimestampSecond(Some(value), _) => {
-                self.append_value(value);
-            }
-            ScalarValue::TimestampSecond(None, _) => {
-                self.append_null();
-            }
-            _ => {
-                return Err(datafusion::error::DataFusionError::Internal(
-                    "Invalid scalar value for TimestampSecondBuilder".to_string(),
-                ));
+            ScalarValue::Map(map) => {
+                let is_valid = append_map_entries(
+                    &self.data_type,
+                    map.as_ref(),
+                    &mut self.builder.builder,
+                    true,
+                )?;
+                self.builder.builder.append(is_valid)?;
             }
+            ScalarValue::Null => self.builder.builder.append(false)?,
+            value => return Err(type_mismatch(self.data_type(), &value)),
         }
         Ok(())
     }
 }
 
+impl ArrayBuilder for Box<dyn AppendableBuilder> {
+    fn len(&self) -> usize {
+        self.as_ref().len()
+    }
+
+    fn finish(&mut self) -> datafusion::arrow::array::ArrayRef {
+        self.as_mut().finish()
+    }
+
+    fn finish_cloned(&self) -> datafusion::arrow::array::ArrayRef {
+        self.as_ref().finish_cloned()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self.as_ref().as_any()
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self.as_mut().as_any_mut()
+    }
+
+    fn into_box_any(self: Box<Self>) -> Box<dyn std::any::Any> {
+        self
+    }
+}
+
+impl AppendableBuilder for Box<dyn AppendableBuilder> {
+    fn data_type(&self) -> DataType {
+        self.as_ref().data_type()
+    }
+
+    fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
+        self.as_mut().append_scalar_value(value)
+    }
+
+    fn append_scalar_value_cast(
+        &mut self,
+        value: ScalarValue,
+        options: &CastOptions,
+    ) -> Result<()> {
+        self.as_mut().append_scalar_value_cast(value, options)
+    }
+
+    fn append_scalar_n(&mut self, value: ScalarValue, n: usize) -> Result<()> {
+        self.as_mut().append_scalar_n(value, n)
+    }
+
+    fn append_nulls(&mut self, n: usize) -> Result<()> {
+        self.as_mut().append_nulls(n)
+    }
+
+    fn append_array_slice(&mut self, array: &ArrayRef, offset: usize, len: usize) -> Result<()> {
+        self.as_mut().append_array_slice(array, offset, len)
+    }
+}
+
 impl_builder_append!(datafusion::arrow::array::BooleanBuilder, Boolean);
 
+impl_builder_append!(datafusion::arrow::array::Decimal128Builder, Decimal128, ..);
+
+impl_builder_append!(datafusion::arrow::array::Decimal256Builder, Decimal256, ..);
+
+impl_builder_append!(
+    datafusion::arrow::array::TimestampSecondBuilder,
+    TimestampSecond,
+    ..
+);
+
+impl_builder_append!(
+    datafusion::arrow::array::TimestampMillisecondBuilder,
+    TimestampMillisecond,
+    ..
+);
+
+impl_builder_append!(
+    datafusion::arrow::array::TimestampMicrosecondBuilder,
+    TimestampMicrosecond,
+    ..
+);
+
+impl_builder_append!(
+    datafusion::arrow::array::TimestampNanosecondBuilder,
+    TimestampNanosecond,
+    ..
+);
+
 impl_builder_append!(datafusion::arrow::array::Float16Builder, Float16);
 
 impl_builder_append!(datafusion::arrow::array::Float32Builder, Float32);
@@ -413,6 +946,10 @@ impl_builder_append!(datafusion::arrow::array::BinaryBuilder, Binary);
 
 impl_builder_append!(datafusion::arrow::array::LargeBinaryBuilder, LargeBinary);
 
+impl_builder_append!(datafusion::arrow::array::StringViewBuilder, Utf8View);
+
+impl_builder_append!(datafusion::arrow::array::BinaryViewBuilder, BinaryView);
+
 impl_builder_append!(datafusion::arrow::array::Date32Builder, Date32);
 
 impl_builder_append!(datafusion::arrow::array::Date64Builder, Date64);
@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::{
+    make_array, Array, ArrayBuilder, ArrayData, ArrayRef, Int16Array, Int32Array, Int64Array,
+    RunArray,
+};
+use datafusion::arrow::compute::CastOptions;
+use datafusion::arrow::datatypes::{DataType, Int16Type, Int32Type, Int64Type, RunEndIndexType};
+use datafusion::error::Result;
+use datafusion::scalar::ScalarValue;
+
+use super::{cast_scalar, make_builder, scalar_from_array, AppendableBuilder};
+
+/// Builder of run-end encoded arrays with values of any type supported by `make_builder`.
+/// DataFusion has no scalar for run-end encoded values, so it is appended the scalars of the value type.
+/// A value equal to the previous one extends the current run instead of starting a new one.
+pub struct RunEndEncodedBuilder {
+    data_type: DataType,
+    run_end_type: DataType,
+    value_type: DataType,
+    run_ends: Vec<i64>,
+    values: Box<dyn AppendableBuilder>,
+    last: Option<ScalarValue>,
+    max_len: i64,
+}
+
+impl RunEndEncodedBuilder {
+    pub fn new(data_type: &DataType, capacity: usize) -> Result<Self> {
+        let DataType::RunEndEncoded(run_ends, values) = data_type else {
+            return Err(datafusion::error::DataFusionError::Internal(format!(
+                "Data type {data_type:?} is not run-end encoded"
+            )));
+        };
+        let max_len = match run_ends.data_type() {
+            DataType::Int16 => i16::MAX as i64,
+            DataType::Int32 => i32::MAX as i64,
+            DataType::Int64 => i64::MAX,
+            t => {
+                return Err(datafusion::error::DataFusionError::Execution(format!(
+                    "Run end type {t:?} is not supported"
+                )))
+            }
+        };
+
+        Ok(Self {
+            data_type: data_type.clone(),
+            run_end_type: run_ends.data_type().clone(),
+            value_type: values.data_type().clone(),
+            run_ends: vec![],
+            values: make_builder(values.data_type(), capacity)?,
+            last: None,
+            max_len,
+        })
+    }
+
+    fn build(&self, values: ArrayRef) -> ArrayRef {
+        // run ends never exceed `max_len`, so the conversions are lossless
+        let run_ends: ArrayRef = match self.run_end_type {
+            DataType::Int16 => Arc::new(Int16Array::from_iter_values(
+                self.run_ends.iter().map(|end| *end as i16),
+            )),
+            DataType::Int32 => Arc::new(Int32Array::from_iter_values(
+                self.run_ends.iter().map(|end| *end as i32),
+            )),
+            _ => Arc::new(Int64Array::from_iter_values(self.run_ends.iter().copied())),
+        };
+
+        let data = ArrayData::builder(self.data_type.clone())
+            .len(self.len())
+            .add_child_data(run_ends.to_data())
+            .add_child_data(values.to_data())
+            .build()
+            .expect("run ends and values are built consistently");
+        make_array(data)
+    }
+}
+
+impl ArrayBuilder for RunEndEncodedBuilder {
+    fn len(&self) -> usize {
+        self.run_ends.last().copied().unwrap_or(0) as usize
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        let values = self.values.finish();
+        let array = self.build(values);
+        self.run_ends.clear();
+        self.last = None;
+        array
+    }
+
+    fn finish_cloned(&self) -> ArrayRef {
+        self.build(self.values.finish_cloned())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn into_box_any(self: Box<Self>) -> Box<dyn std::any::Any> {
+        self
+    }
+}
+
+impl AppendableBuilder for RunEndEncodedBuilder {
+    fn data_type(&self) -> DataType {
+        self.data_type.clone()
+    }
+
+    fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
+        self.append_scalar_n(value, 1)
+    }
+
+    /// The values are cast to the value type, arrow cannot cast to run-end encoded types
+    fn append_scalar_value_cast(
+        &mut self,
+        value: ScalarValue,
+        options: &CastOptions,
+    ) -> Result<()> {
+        let value = cast_scalar(value, &self.value_type, options)?;
+        self.append_scalar_n(value, 1)
+    }
+
+    fn append_scalar_n(&mut self, value: ScalarValue, n: usize) -> Result<()> {
+        if n == 0 {
+            return Ok(());
+        }
+        // all nulls of the run have the same representation
+        let value = match value {
+            ScalarValue::Null => ScalarValue::try_from(&self.value_type)?,
+            value => value,
+        };
+        let len = self.len() as i64;
+        if self.max_len - len < n as i64 {
+            return Err(datafusion::error::DataFusionError::Execution(format!(
+                "Run-end encoded array of {:?} is full",
+                self.data_type
+            )));
+        }
+
+        match self.run_ends.last_mut() {
+            Some(end) if self.last.as_ref() == Some(&value) => *end += n as i64,
+            _ => {
+                self.values.append_scalar_value(value.clone())?;
+                self.run_ends.push(len + n as i64);
+                self.last = Some(value);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Logical value at `index` of a run-end encoded array
+pub(super) fn run_end_value(array: &dyn Array, index: usize) -> Result<ScalarValue> {
+    fn value<R: RunEndIndexType>(array: &dyn Array, index: usize) -> Result<ScalarValue> {
+        let array = array
+            .as_any()
+            .downcast_ref::<RunArray<R>>()
+            .ok_or_else(|| {
+                datafusion::error::DataFusionError::Internal(format!(
+                    "Data type {:?} is not run-end encoded",
+                    array.data_type()
+                ))
+            })?;
+        scalar_from_array(array.values(), array.get_physical_index(index))
+    }
+
+    match array.data_type() {
+        DataType::RunEndEncoded(run_ends, _) => match run_ends.data_type() {
+            DataType::Int16 => value::<Int16Type>(array, index),
+            DataType::Int32 => value::<Int32Type>(array, index),
+            _ => value::<Int64Type>(array, index),
+        },
+        t => Err(datafusion::error::DataFusionError::Internal(format!(
+            "Data type {t:?} is not run-end encoded"
+        ))),
+    }
+}
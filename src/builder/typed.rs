@@ -0,0 +1,270 @@
+use std::fmt;
+
+use datafusion::arrow::array::{
+    make_array, Array, ArrayBuilder, ArrayData, ArrayRef, ArrowPrimitiveType, BooleanBuilder,
+    GenericByteBuilder, GenericByteViewBuilder, PrimitiveBuilder,
+};
+use datafusion::arrow::compute::CastOptions;
+use datafusion::arrow::datatypes::{ByteArrayType, ByteViewType, DataType};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::scalar::ScalarValue;
+
+use super::{cast_scalar, AppendableBuilder};
+
+/// Error of appending a value of one type to a builder of another.
+/// It is returned as `DataFusionError::External`, so callers can downcast it to see both types.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeMismatch {
+    /// data type of the builder
+    pub expected: DataType,
+    /// data type of the appended scalar value or array
+    pub actual: DataType,
+}
+
+impl fmt::Display for TypeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cannot append a value of type {:?} to a builder of {:?}",
+            self.actual, self.expected
+        )
+    }
+}
+
+impl std::error::Error for TypeMismatch {}
+
+impl From<TypeMismatch> for DataFusionError {
+    fn from(error: TypeMismatch) -> Self {
+        DataFusionError::External(Box::new(error))
+    }
+}
+
+pub(crate) fn type_mismatch(expected: DataType, value: &ScalarValue) -> DataFusionError {
+    TypeMismatch {
+        expected,
+        actual: value.data_type(),
+    }
+    .into()
+}
+
+pub(crate) fn array_type_mismatch(expected: DataType, array: &dyn Array) -> DataFusionError {
+    TypeMismatch {
+        expected,
+        actual: array.data_type().clone(),
+    }
+    .into()
+}
+
+/// Checks that a builder of `expected` accepts values of `actual` before anything is appended,
+/// for builders whose children must grow together (e.g. the fields of a struct).
+/// Nested types are compared by their child types, field names and nullability are left to the children.
+pub(crate) fn check_type(expected: &DataType, actual: &DataType) -> Result<()> {
+    if accepts_type(expected, actual) {
+        return Ok(());
+    }
+    Err(TypeMismatch {
+        expected: expected.clone(),
+        actual: actual.clone(),
+    }
+    .into())
+}
+
+fn accepts_type(expected: &DataType, actual: &DataType) -> bool {
+    if actual == &DataType::Null || expected == actual {
+        return true;
+    }
+    match (expected, actual) {
+        // dictionaries and run-end encoded arrays take both their encoded and their plain values
+        (DataType::Dictionary(_, values), DataType::Dictionary(_, actual)) => {
+            accepts_type(values, actual)
+        }
+        (DataType::Dictionary(_, values), actual) => accepts_type(values, actual),
+        (DataType::RunEndEncoded(_, values), actual) => accepts_type(values.data_type(), actual),
+        (DataType::List(expected), DataType::List(actual))
+        | (DataType::LargeList(expected), DataType::LargeList(actual))
+        | (DataType::Map(expected, _), DataType::Map(actual, _)) => {
+            accepts_type(expected.data_type(), actual.data_type())
+        }
+        (DataType::FixedSizeList(expected, len), DataType::FixedSizeList(actual, actual_len)) => {
+            len == actual_len && accepts_type(expected.data_type(), actual.data_type())
+        }
+        (DataType::Struct(expected), DataType::Struct(actual)) => {
+            expected.len() == actual.len()
+                && expected
+                    .iter()
+                    .zip(actual.iter())
+                    .all(|(expected, actual)| {
+                        accepts_type(expected.data_type(), actual.data_type())
+                    })
+        }
+        (DataType::Union(expected, _), DataType::Union(actual, _)) => {
+            expected.len() == actual.len()
+                && expected.iter().zip(actual.iter()).all(
+                    |((expected_id, expected), (actual_id, actual))| {
+                        expected_id == actual_id
+                            && accepts_type(expected.data_type(), actual.data_type())
+                    },
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Data type of the arrow builders whose type has no parameters.
+/// Decimal and timestamp builders have a type parameter arrow doesn't expose, `make_builder` wraps them in `TypedBuilder`.
+pub(crate) trait FixedDataType {
+    fn fixed_data_type() -> DataType;
+}
+
+impl<T: ArrowPrimitiveType> FixedDataType for PrimitiveBuilder<T> {
+    fn fixed_data_type() -> DataType {
+        T::DATA_TYPE
+    }
+}
+
+impl FixedDataType for BooleanBuilder {
+    fn fixed_data_type() -> DataType {
+        DataType::Boolean
+    }
+}
+
+impl<T: ByteArrayType> FixedDataType for GenericByteBuilder<T> {
+    fn fixed_data_type() -> DataType {
+        T::DATA_TYPE
+    }
+}
+
+impl<T: ByteViewType> FixedDataType for GenericByteViewBuilder<T> {
+    fn fixed_data_type() -> DataType {
+        T::DATA_TYPE
+    }
+}
+
+/// Keeps the data type of an arrow builder that doesn't report it (decimals, timestamps, fixed size binaries
+/// and the nested builders), so `data_type()` doesn't have to build the array.
+/// Values of a flat type must match it exactly, e.g. a decimal of another precision or a timestamp in another
+/// timezone is rejected instead of being appended with the builder's parameters.
+/// The builder must build arrays of `data_type`, e.g. `Decimal128Builder::new().with_data_type(data_type)`.
+pub struct TypedBuilder<B> {
+    pub(crate) data_type: DataType,
+    pub(crate) builder: B,
+}
+
+impl<B: ArrayBuilder> TypedBuilder<B> {
+    pub fn new(data_type: DataType, builder: B) -> Self {
+        Self { data_type, builder }
+    }
+
+    /// arrow's nested builders drop the metadata of some fields (e.g. map keys),
+    /// the finished array gets it back from the data type
+    fn retyped(&self, array: ArrayRef) -> ArrayRef {
+        if array.data_type() == &self.data_type
+            || !array.data_type().equals_datatype(&self.data_type)
+        {
+            return array;
+        }
+        make_array(retype(array.to_data(), &self.data_type))
+    }
+
+    pub(crate) fn check(&self, actual: &DataType) -> Result<()> {
+        if self.data_type.is_nested() || actual == &DataType::Null || actual == &self.data_type {
+            return Ok(());
+        }
+        Err(TypeMismatch {
+            expected: self.data_type.clone(),
+            actual: actual.clone(),
+        }
+        .into())
+    }
+}
+
+/// Replaces the data type of `data` and of its children with `data_type` that differs only in field names and metadata
+fn retype(data: ArrayData, data_type: &DataType) -> ArrayData {
+    if data.data_type() == data_type {
+        return data;
+    }
+    let child_types = match data_type {
+        DataType::List(field)
+        | DataType::LargeList(field)
+        | DataType::FixedSizeList(field, _)
+        | DataType::Map(field, _) => vec![field.data_type()],
+        DataType::Struct(fields) => fields.iter().map(|field| field.data_type()).collect(),
+        DataType::Union(fields, _) => fields.iter().map(|(_, field)| field.data_type()).collect(),
+        DataType::RunEndEncoded(run_ends, values) => vec![run_ends.data_type(), values.data_type()],
+        DataType::Dictionary(_, values) => vec![values.as_ref()],
+        _ => vec![],
+    };
+    let children = data
+        .child_data()
+        .iter()
+        .zip(child_types)
+        .map(|(child, data_type)| retype(child.clone(), data_type))
+        .collect();
+    data.into_builder()
+        .data_type(data_type.clone())
+        .child_data(children)
+        .build()
+        .expect("the data types differ only in field names and metadata")
+}
+
+impl<B: ArrayBuilder> ArrayBuilder for TypedBuilder<B> {
+    fn len(&self) -> usize {
+        self.builder.len()
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        let array = self.builder.finish();
+        self.retyped(array)
+    }
+
+    fn finish_cloned(&self) -> ArrayRef {
+        self.retyped(self.builder.finish_cloned())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn into_box_any(self: Box<Self>) -> Box<dyn std::any::Any> {
+        self
+    }
+}
+
+impl<B: AppendableBuilder> AppendableBuilder for TypedBuilder<B> {
+    fn data_type(&self) -> DataType {
+        self.data_type.clone()
+    }
+
+    fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
+        self.check(&value.data_type())?;
+        self.builder.append_scalar_value(value)
+    }
+
+    fn append_scalar_value_cast(
+        &mut self,
+        value: ScalarValue,
+        options: &CastOptions,
+    ) -> Result<()> {
+        // the wrapped builder would read its type from the built array
+        let value = cast_scalar(value, &self.data_type, options)?;
+        self.builder.append_scalar_value(value)
+    }
+
+    fn append_scalar_n(&mut self, value: ScalarValue, n: usize) -> Result<()> {
+        self.check(&value.data_type())?;
+        self.builder.append_scalar_n(value, n)
+    }
+
+    fn append_nulls(&mut self, n: usize) -> Result<()> {
+        self.builder.append_nulls(n)
+    }
+
+    fn append_array_slice(&mut self, array: &ArrayRef, offset: usize, len: usize) -> Result<()> {
+        self.check(array.data_type())?;
+        self.builder.append_array_slice(array, offset, len)
+    }
+}
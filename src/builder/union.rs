@@ -0,0 +1,176 @@
+use std::sync::Arc;
+
+use datafusion::arrow::array::{Array, ArrayBuilder, ArrayRef, UnionArray};
+use datafusion::arrow::buffer::ScalarBuffer;
+use datafusion::arrow::datatypes::{DataType, UnionFields, UnionMode};
+use datafusion::error::Result;
+use datafusion::scalar::ScalarValue;
+
+use super::{make_builder, scalar_from_array, type_mismatch, AppendableBuilder};
+
+/// arrow's UnionBuilder supports only primitive children, this one creates the children with `make_builder`.
+/// In sparse mode every child gets a slot for every row, the children not selected by the type id get a null.
+pub struct UnionArrayBuilder {
+    fields: UnionFields,
+    mode: UnionMode,
+    type_ids: Vec<i8>,
+    offsets: Vec<i32>,
+    children: Vec<(DataType, Box<dyn AppendableBuilder>)>,
+}
+
+impl UnionArrayBuilder {
+    pub fn new(fields: &UnionFields, mode: UnionMode, capacity: usize) -> Result<Self> {
+        let children = fields
+            .iter()
+            .map(|(_, field)| {
+                let builder = make_builder(field.data_type(), capacity)?;
+                Ok((field.data_type().clone(), builder))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            fields: fields.clone(),
+            mode,
+            type_ids: Vec::with_capacity(capacity),
+            offsets: vec![],
+            children,
+        })
+    }
+
+    fn build(&self, children: Vec<ArrayRef>) -> ArrayRef {
+        let offsets = match self.mode {
+            UnionMode::Dense => Some(ScalarBuffer::from(self.offsets.clone())),
+            UnionMode::Sparse => None,
+        };
+        let array = UnionArray::try_new(
+            self.fields.clone(),
+            ScalarBuffer::from(self.type_ids.clone()),
+            offsets,
+            children,
+        )
+        .expect("type ids, offsets and children are built consistently");
+        Arc::new(array)
+    }
+}
+
+impl ArrayBuilder for UnionArrayBuilder {
+    fn len(&self) -> usize {
+        self.type_ids.len()
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        let children = self
+            .children
+            .iter_mut()
+            .map(|(_, builder)| builder.finish())
+            .collect();
+        let array = self.build(children);
+        self.type_ids.clear();
+        self.offsets.clear();
+        array
+    }
+
+    fn finish_cloned(&self) -> ArrayRef {
+        let children = self
+            .children
+            .iter()
+            .map(|(_, builder)| builder.finish_cloned())
+            .collect();
+        self.build(children)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn into_box_any(self: Box<Self>) -> Box<dyn std::any::Any> {
+        self
+    }
+}
+
+impl AppendableBuilder for UnionArrayBuilder {
+    fn data_type(&self) -> DataType {
+        DataType::Union(self.fields.clone(), self.mode)
+    }
+
+    fn append_scalar_value(&mut self, value: ScalarValue) -> Result<()> {
+        // a union has no nulls of its own, a null is a null value of the first child
+        let (type_id, value) = match value {
+            ScalarValue::Union(Some((type_id, value)), _, _) => (type_id, Some(*value)),
+            ScalarValue::Union(None, _, _) | ScalarValue::Null => {
+                let (type_id, _) = self.fields.iter().next().ok_or_else(|| {
+                    datafusion::error::DataFusionError::Execution(
+                        "Cannot append a null to a union without fields".to_string(),
+                    )
+                })?;
+                (type_id, None)
+            }
+            value => return Err(type_mismatch(self.data_type(), &value)),
+        };
+        let child = self
+            .fields
+            .iter()
+            .position(|(id, _)| id == type_id)
+            .ok_or_else(|| {
+                datafusion::error::DataFusionError::Internal(format!(
+                    "Type id {type_id} is not in the union fields"
+                ))
+            })?;
+
+        // the rejected value of a failed append must not leave the type ids, offsets and children
+        // out of step, so the other children get their nulls only after the selected child took the value
+        let nulls = match self.mode {
+            UnionMode::Sparse => self
+                .children
+                .iter()
+                .map(|(data_type, _)| ScalarValue::try_from(data_type))
+                .collect::<Result<Vec<_>>>()?,
+            UnionMode::Dense => vec![],
+        };
+        let (data_type, builder) = &mut self.children[child];
+        let offset = builder.len() as i32;
+        let value = match value {
+            Some(value) => value,
+            None => ScalarValue::try_from(&*data_type)?,
+        };
+        builder.append_scalar_value(value)?;
+
+        match self.mode {
+            UnionMode::Dense => self.offsets.push(offset),
+            UnionMode::Sparse => {
+                for (i, ((_, builder), null)) in self.children.iter_mut().zip(nulls).enumerate() {
+                    if i != child {
+                        builder.append_scalar_value(null)?;
+                    }
+                }
+            }
+        }
+        self.type_ids.push(type_id);
+        Ok(())
+    }
+}
+
+/// Value at `index` of a union array, DataFusion can create only null union scalars from arrays
+pub(super) fn union_value(array: &dyn Array, index: usize) -> Result<ScalarValue> {
+    let DataType::Union(fields, mode) = array.data_type() else {
+        return Err(datafusion::error::DataFusionError::Internal(format!(
+            "Data type {:?} is not a union",
+            array.data_type()
+        )));
+    };
+    let union = array.as_any().downcast_ref::<UnionArray>().ok_or_else(|| {
+        datafusion::error::DataFusionError::Internal("Expected a union array".to_string())
+    })?;
+    let type_id = union.type_id(index);
+    let value = scalar_from_array(union.child(type_id), union.value_offset(index))?;
+
+    Ok(ScalarValue::Union(
+        Some((type_id, Box::new(value))),
+        fields.clone(),
+        *mode,
+    ))
+}
@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use datafusion::arrow::datatypes::{
+    DataType, Field, Fields, TimeUnit, DECIMAL128_MAX_PRECISION, DECIMAL256_MAX_PRECISION,
+};
+
+/// Finds the common type of all `types` following Spark's type widening rules for greatest()/least().
+/// Returns `None` if the types are not comparable.
+///
+/// `ansi` selects the rules of `spark.sql.ansi.enabled=true`, where strings are implicitly cast
+/// to the other side's type. Without it, Spark does not promote strings for greatest()/least()
+/// and such a mix is rejected.
+pub fn spark_common_type(types: &[DataType], ansi: bool) -> Option<DataType> {
+    let (first, rest) = types.split_first()?;
+    rest.iter()
+        .try_fold(first.clone(), |acc, typ| wider_type(&acc, typ, ansi))
+}
+
+/// Spark's `findWiderTypeForTwo` (or its ANSI counterpart) mapped to arrow types
+fn wider_type(left: &DataType, right: &DataType, ansi: bool) -> Option<DataType> {
+    use DataType::*;
+
+    if left == right {
+        return Some(left.clone());
+    }
+
+    match (left, right) {
+        (Null, t) | (t, Null) => Some(t.clone()),
+        // dictionary encoding is not a Spark type, differently encoded values compare as plain values
+        (Dictionary(_, l), r) | (r, Dictionary(_, l)) => wider_type(l, r, ansi),
+
+        (Utf8, LargeUtf8) | (LargeUtf8, Utf8) => Some(LargeUtf8),
+        (Binary, LargeBinary) | (LargeBinary, Binary) => Some(LargeBinary),
+        // views win over the offset based layouts, as in DataFusion's string coercion
+        (Utf8View, Utf8 | LargeUtf8) | (Utf8 | LargeUtf8, Utf8View) => Some(Utf8View),
+        (BinaryView, Binary | LargeBinary) | (Binary | LargeBinary, BinaryView) => Some(BinaryView),
+        (Utf8 | LargeUtf8 | Utf8View, other) | (other, Utf8 | LargeUtf8 | Utf8View) if ansi => {
+            ansi_string_promotion(other)
+        }
+
+        (l, r) if is_decimal(l) || is_decimal(r) => wider_decimal_type(l, r),
+        (l, r) if l.is_numeric() && r.is_numeric() => wider_numeric_type(l, r),
+
+        (Date32, Date64) | (Date64, Date32) => Some(Date64),
+        (Date32 | Date64, Timestamp(unit, tz)) | (Timestamp(unit, tz), Date32 | Date64) => {
+            Some(Timestamp(*unit, tz.clone()))
+        }
+        (Timestamp(l_unit, l_tz), Timestamp(r_unit, r_tz)) => {
+            // timestamps with a time zone win over timestamps without it (Spark's TIMESTAMP_NTZ)
+            Some(Timestamp(
+                finer_unit(l_unit, r_unit),
+                l_tz.clone().or(r_tz.clone()),
+            ))
+        }
+
+        (List(l), List(r)) => wider_field(l, r, ansi).map(List),
+        (LargeList(l), LargeList(r)) => wider_field(l, r, ansi).map(LargeList),
+        (List(l), LargeList(r)) | (LargeList(l), List(r)) => wider_field(l, r, ansi).map(LargeList),
+        (Struct(l), Struct(r)) => wider_struct_fields(l, r, ansi).map(Struct),
+
+        _ => None,
+    }
+}
+
+/// In ANSI mode a string is implicitly cast to the type of the other argument,
+/// with any fractional type (also a decimal) it is cast to a double
+fn ansi_string_promotion(other: &DataType) -> Option<DataType> {
+    match other {
+        t if t.is_integer() => Some(DataType::Int64),
+        t if t.is_floating() || is_decimal(t) => Some(DataType::Float64),
+        DataType::Interval(_) | DataType::Duration(_) => None,
+        t if t.is_nested() => None,
+        t => Some(t.clone()),
+    }
+}
+
+fn is_decimal(datatype: &DataType) -> bool {
+    matches!(
+        datatype,
+        DataType::Decimal128(_, _) | DataType::Decimal256(_, _)
+    )
+}
+
+/// Spark's `numericPrecedence`. Unsigned integers don't exist in Spark, they take the place
+/// of the smallest signed type that holds all of their values.
+fn numeric_rank(datatype: &DataType) -> Option<u8> {
+    use DataType::*;
+
+    Some(match datatype {
+        Int8 => 0,
+        UInt8 | Int16 => 1,
+        UInt16 | Int32 => 2,
+        UInt32 | Int64 => 3,
+        Float16 => 4,
+        Float32 => 5,
+        Float64 => 6,
+        _ => return None,
+    })
+}
+
+fn wider_numeric_type(left: &DataType, right: &DataType) -> Option<DataType> {
+    use DataType::*;
+
+    match (left, right) {
+        // both unsigned, so the wider one is enough
+        (l, r) if l.is_unsigned_integer() && r.is_unsigned_integer() => {
+            Some(if l.primitive_width() >= r.primitive_width() {
+                l.clone()
+            } else {
+                r.clone()
+            })
+        }
+        // no signed integer can hold all of UInt64 values
+        (UInt64, r) | (r, UInt64) if r.is_integer() => Some(Decimal128(20, 0)),
+        (UInt64, _) | (_, UInt64) => Some(Float64),
+        // a half float holds integers exactly only up to 2048, Spark has no such type to follow
+        (Float16, r) | (r, Float16) if r.is_integer() => Some(Float64),
+        (l, r) => {
+            let rank = numeric_rank(l)?.max(numeric_rank(r)?);
+            Some(match rank {
+                0 => Int8,
+                1 => Int16,
+                2 => Int32,
+                3 => Int64,
+                4 => Float16,
+                5 => Float32,
+                _ => Float64,
+            })
+        }
+    }
+}
+
+/// Spark's `DecimalType.forType` for integers, the decimal that holds all of the type's values
+fn integer_as_decimal(datatype: &DataType) -> Option<(u8, i8)> {
+    use DataType::*;
+
+    Some(match datatype {
+        Int8 | UInt8 => (3, 0),
+        Int16 | UInt16 => (5, 0),
+        Int32 | UInt32 => (10, 0),
+        Int64 | UInt64 => (20, 0),
+        _ => return None,
+    })
+}
+
+fn decimal_parts(datatype: &DataType) -> Option<(u8, i8)> {
+    match datatype {
+        DataType::Decimal128(p, s) | DataType::Decimal256(p, s) => Some((*p, *s)),
+        t => integer_as_decimal(t),
+    }
+}
+
+/// Decimal with decimal or integer gives a decimal that holds both integral and fractional parts,
+/// decimal with a floating point number gives a double.
+fn wider_decimal_type(left: &DataType, right: &DataType) -> Option<DataType> {
+    if left.is_floating() || right.is_floating() {
+        return Some(DataType::Float64);
+    }
+
+    let (l_precision, l_scale) = decimal_parts(left)?;
+    let (r_precision, r_scale) = decimal_parts(right)?;
+
+    let scale = l_scale.max(r_scale);
+    let integral = (l_precision as i16 - l_scale as i16).max(r_precision as i16 - r_scale as i16);
+    let precision = (integral + scale as i16).max(1) as u8;
+
+    let is_256 =
+        matches!(left, DataType::Decimal256(_, _)) || matches!(right, DataType::Decimal256(_, _));
+    if is_256 {
+        let precision = precision.min(DECIMAL256_MAX_PRECISION);
+        Some(DataType::Decimal256(precision, scale.min(precision as i8)))
+    } else {
+        let precision = precision.min(DECIMAL128_MAX_PRECISION);
+        Some(DataType::Decimal128(precision, scale.min(precision as i8)))
+    }
+}
+
+fn finer_unit(left: &TimeUnit, right: &TimeUnit) -> TimeUnit {
+    let rank = |unit: &TimeUnit| match unit {
+        TimeUnit::Second => 0,
+        TimeUnit::Millisecond => 1,
+        TimeUnit::Microsecond => 2,
+        TimeUnit::Nanosecond => 3,
+    };
+    if rank(left) >= rank(right) {
+        *left
+    } else {
+        *right
+    }
+}
+
+fn wider_field(left: &Field, right: &Field, ansi: bool) -> Option<Arc<Field>> {
+    let datatype = wider_type(left.data_type(), right.data_type(), ansi)?;
+    Some(Arc::new(
+        left.clone()
+            .with_data_type(datatype)
+            .with_nullable(left.is_nullable() || right.is_nullable()),
+    ))
+}
+
+/// structs are comparable if they have the same number of fields with equal (case insensitive) names
+fn wider_struct_fields(left: &Fields, right: &Fields, ansi: bool) -> Option<Fields> {
+    if left.len() != right.len() {
+        return None;
+    }
+
+    left.iter()
+        .zip(right.iter())
+        .map(|(l, r)| {
+            if !l.name().eq_ignore_ascii_case(r.name()) {
+                return None;
+            }
+            wider_field(l, r, ansi)
+        })
+        .collect::<Option<Vec<_>>>()
+        .map(Fields::from)
+}
@@ -0,0 +1,141 @@
+use std::cmp::Ordering;
+
+use datafusion::arrow::array::{
+    make_array, Array, ArrayRef, AsArray, BooleanArray, BooleanBufferBuilder,
+};
+use datafusion::arrow::compute::{interleave, nullif};
+use datafusion::arrow::datatypes::DataType;
+use datafusion::error::Result;
+use datafusion::scalar::ScalarValue;
+
+use crate::greatest::ExtremumOptions;
+use crate::ordering::{compare_nulls_first, is_nan, Collation, NanOrdering};
+
+/// Position of every dictionary value in the Spark ordering, equal values get equal ranks.
+/// Null values have no rank.
+struct ValueRanks {
+    ranks: Vec<Option<usize>>,
+    nans: Vec<bool>,
+}
+
+impl ValueRanks {
+    fn try_new(values: &ArrayRef, collation: Collation) -> Result<Self> {
+        let scalars = (0..values.len())
+            .map(|i| ScalarValue::try_from_array(values, i))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut order = (0..scalars.len()).collect::<Vec<_>>();
+        order.sort_by(|l, r| {
+            compare_nulls_first(&scalars[*l], &scalars[*r], collation).unwrap_or(Ordering::Equal)
+        });
+
+        let mut ranks = vec![None; scalars.len()];
+        let mut rank = 0;
+        for (i, index) in order.iter().enumerate() {
+            if scalars[*index].is_null() {
+                continue;
+            }
+            if i > 0
+                && compare_nulls_first(&scalars[order[i - 1]], &scalars[*index], collation)
+                    != Some(Ordering::Equal)
+            {
+                rank += 1;
+            }
+            ranks[*index] = Some(rank);
+        }
+
+        Ok(Self {
+            ranks,
+            nans: scalars.iter().map(is_nan).collect(),
+        })
+    }
+
+    /// returns true if the value at `value` should replace the current `best` one, as `replaces` does for scalars
+    fn replaces(
+        &self,
+        value: usize,
+        best: usize,
+        target: Ordering,
+        options: ExtremumOptions,
+    ) -> bool {
+        if options.nan_ordering == NanOrdering::IgnoreNan {
+            match (self.nans[value], self.nans[best]) {
+                (true, _) => return false,
+                (false, true) => return true,
+                (false, false) => {}
+            }
+        }
+        self.ranks[value].cmp(&self.ranks[best]) == target
+    }
+}
+
+/// Row-wise extremum of dictionary arrays that share the same values. Only the keys are compared
+/// (through the precomputed ranks of the values) and the result reuses the shared dictionary.
+/// Returns `None` if the arguments are not such dictionaries, so their values have to be compared directly.
+pub(crate) fn extremum_with_shared_dictionary(
+    args: &[ArrayRef],
+    target: Ordering,
+    options: ExtremumOptions,
+) -> Result<Option<ArrayRef>> {
+    let datatype = args[0].data_type();
+    if !matches!(datatype, DataType::Dictionary(_, _)) {
+        return Ok(None);
+    }
+    let dictionaries = args
+        .iter()
+        .map(|arg| arg.as_any_dictionary_opt())
+        .collect::<Option<Vec<_>>>();
+    let Some(dictionaries) = dictionaries else {
+        return Ok(None);
+    };
+    let values = dictionaries[0].values();
+    let shared = dictionaries
+        .iter()
+        .all(|dictionary| dictionary.values().to_data().ptr_eq(&values.to_data()));
+    if !shared || values.is_empty() {
+        return Ok(None);
+    }
+
+    let ranks = ValueRanks::try_new(values, options.collation)?;
+    let keys = dictionaries
+        .iter()
+        .map(|dictionary| dictionary.normalized_keys())
+        .collect::<Vec<_>>();
+
+    let rows = args[0].len();
+    let mut indices = Vec::with_capacity(rows);
+    let mut all_null = BooleanBufferBuilder::new(rows);
+    for row in 0..rows {
+        let mut best: Option<(usize, usize)> = None;
+        for (arg, (dictionary, keys)) in dictionaries.iter().zip(&keys).enumerate() {
+            let value = keys[row];
+            if dictionary.keys().is_null(row) || ranks.ranks[value].is_none() {
+                continue;
+            }
+            if best.map_or(true, |(_, best)| {
+                ranks.replaces(value, best, target, options)
+            }) {
+                best = Some((arg, value));
+            }
+        }
+        indices.push((best.map_or(0, |(arg, _)| arg), row));
+        all_null.append(best.is_none());
+    }
+
+    let key_arrays = dictionaries
+        .iter()
+        .map(|dictionary| dictionary.keys())
+        .collect::<Vec<_>>();
+    let result_keys = interleave(&key_arrays, &indices)?;
+    // rows where all arguments are null (or point to null values) become null keys
+    let all_null = BooleanArray::new(all_null.finish(), None);
+    let result_keys = nullif(&result_keys, &all_null)?;
+
+    let data = result_keys
+        .to_data()
+        .into_builder()
+        .data_type(datatype.clone())
+        .child_data(vec![values.to_data()])
+        .build()?;
+    Ok(Some(make_array(data)))
+}
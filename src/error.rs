@@ -0,0 +1,166 @@
+use std::fmt;
+
+use datafusion::arrow::datatypes::DataType;
+use datafusion::error::DataFusionError;
+
+const DOC_ROOT: &str = "https://spark.apache.org/docs/latest";
+
+/// Error raised by Spark's analyzer for the same call, with its error class, SQLSTATE and message parameters.
+/// The message is rendered like Spark's, e.g.
+/// ``[WRONG_NUM_ARGS.WITHOUT_SUGGESTION] The `greatest` requires > 1 parameters but the actual number is 1. ...``,
+/// so tests written against Spark can compare either the message or the parameters.
+/// It is returned as `DataFusionError::External`, so callers can downcast it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparkError {
+    error_class: &'static str,
+    sql_state: &'static str,
+    template: &'static str,
+    parameters: Vec<(&'static str, String)>,
+}
+
+impl SparkError {
+    /// `WRONG_NUM_ARGS.WITHOUT_SUGGESTION`: `function_name` got `actual` arguments, `expected` describes
+    /// the accepted number the way Spark does, e.g. `> 1`
+    pub fn wrong_num_args(function_name: &str, expected: &str, actual: usize) -> Self {
+        Self {
+            error_class: "WRONG_NUM_ARGS.WITHOUT_SUGGESTION",
+            sql_state: "42605",
+            template: "The <functionName> requires <expectedNum> parameters but the actual number is <actualNum>. \
+                       Please, refer to '<docroot>/sql-ref-functions.html' for a fix.",
+            parameters: vec![
+                ("functionName", sql_id(function_name)),
+                ("expectedNum", expected.to_string()),
+                ("actualNum", actual.to_string()),
+                ("docroot", DOC_ROOT.to_string()),
+            ],
+        }
+    }
+
+    /// `DATATYPE_MISMATCH.DATA_DIFF_TYPES`: the arguments of `function_name` have no common type.
+    /// `args` are the SQL of the arguments, rendered into the call as `sqlExpr`, e.g. `"greatest(a, b)"`.
+    pub fn data_diff_types(function_name: &str, args: &[String], types: &[DataType]) -> Self {
+        let types = types
+            .iter()
+            .map(|data_type| format!("\"{}\"", sql_type(data_type)))
+            .collect::<Vec<_>>();
+        Self {
+            error_class: "DATATYPE_MISMATCH.DATA_DIFF_TYPES",
+            sql_state: "42K09",
+            template: "Cannot resolve <sqlExpr> due to data type mismatch: \
+                       Input to <functionName> should all be the same type, but it's <dataType>.",
+            parameters: vec![
+                (
+                    "sqlExpr",
+                    format!("\"{function_name}({})\"", args.join(", ")),
+                ),
+                ("functionName", sql_id(function_name)),
+                ("dataType", format!("[{}]", types.join(", "))),
+            ],
+        }
+    }
+
+    /// Spark error class including the subclass, e.g. `DATATYPE_MISMATCH.DATA_DIFF_TYPES`
+    pub fn error_class(&self) -> &str {
+        self.error_class
+    }
+
+    /// SQLSTATE of the error class
+    pub fn sql_state(&self) -> &str {
+        self.sql_state
+    }
+
+    /// Message parameters in the order of the message, quoted as Spark quotes them
+    pub fn parameters(&self) -> &[(&'static str, String)] {
+        &self.parameters
+    }
+
+    /// Value of the message parameter `name`
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(parameter, _)| *parameter == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl fmt::Display for SparkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = self
+            .parameters
+            .iter()
+            .fold(self.template.to_string(), |message, (name, value)| {
+                message.replace(&format!("<{name}>"), value)
+            });
+        write!(
+            f,
+            "[{}] {message} SQLSTATE: {}",
+            self.error_class, self.sql_state
+        )
+    }
+}
+
+impl std::error::Error for SparkError {}
+
+impl From<SparkError> for DataFusionError {
+    fn from(error: SparkError) -> Self {
+        DataFusionError::External(Box::new(error))
+    }
+}
+
+/// SQL of arguments known only by their types, e.g. when a function is validated with `return_type`
+pub(crate) fn unknown_args(len: usize) -> Vec<String> {
+    vec!["?".to_string(); len]
+}
+
+/// Spark's `toSQLId`
+fn sql_id(name: &str) -> String {
+    format!("`{name}`")
+}
+
+/// Spark's SQL name of the type an arrow type is read as
+fn sql_type(data_type: &DataType) -> String {
+    match data_type {
+        DataType::Null => "VOID".to_string(),
+        DataType::Boolean => "BOOLEAN".to_string(),
+        DataType::Int8 => "TINYINT".to_string(),
+        DataType::Int16 | DataType::UInt8 => "SMALLINT".to_string(),
+        DataType::Int32 | DataType::UInt16 => "INT".to_string(),
+        DataType::Int64 | DataType::UInt32 => "BIGINT".to_string(),
+        DataType::UInt64 => "DECIMAL(20,0)".to_string(),
+        DataType::Float16 | DataType::Float32 => "FLOAT".to_string(),
+        DataType::Float64 => "DOUBLE".to_string(),
+        DataType::Decimal128(precision, scale) | DataType::Decimal256(precision, scale) => {
+            format!("DECIMAL({precision},{scale})")
+        }
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => "STRING".to_string(),
+        DataType::Binary
+        | DataType::LargeBinary
+        | DataType::BinaryView
+        | DataType::FixedSizeBinary(_) => "BINARY".to_string(),
+        DataType::Date32 | DataType::Date64 => "DATE".to_string(),
+        DataType::Timestamp(_, None) => "TIMESTAMP_NTZ".to_string(),
+        DataType::Timestamp(_, Some(_)) => "TIMESTAMP".to_string(),
+        DataType::List(field) | DataType::LargeList(field) | DataType::FixedSizeList(field, _) => {
+            format!("ARRAY<{}>", sql_type(field.data_type()))
+        }
+        DataType::Struct(fields) => format!(
+            "STRUCT<{}>",
+            fields
+                .iter()
+                .map(|field| format!("{}: {}", field.name(), sql_type(field.data_type())))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        DataType::Map(field, _) => match field.data_type() {
+            DataType::Struct(fields) if fields.len() == 2 => format!(
+                "MAP<{}, {}>",
+                sql_type(fields[0].data_type()),
+                sql_type(fields[1].data_type())
+            ),
+            _ => format!("{data_type}").to_uppercase(),
+        },
+        DataType::Dictionary(_, values) => sql_type(values),
+        DataType::RunEndEncoded(_, values) => sql_type(values.data_type()),
+        other => format!("{other}").to_uppercase(),
+    }
+}
@@ -1,65 +1,512 @@
-use datafusion::error::Result;
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use datafusion::arrow::array::{new_null_array, Array, BooleanArray};
+use datafusion::arrow::compute::{interleave, is_null, nullif, or};
+use datafusion::error::{DataFusionError, Result};
 use datafusion::{
     arrow::{array::ArrayRef, datatypes::DataType},
     logical_expr::ColumnarValue,
     scalar::ScalarValue,
 };
 
-use crate::builder::{make_builder, AppendableBuilder};
+use crate::builder::{make_builder, scalar_from_array};
+use crate::coercion::spark_common_type;
+use crate::dictionary::extremum_with_shared_dictionary;
+use crate::error::{unknown_args, SparkError};
+use crate::kernels::{extremum_with_kernels, supports_kernels};
+use crate::ordering::{compare_collated, is_nan, Collation, NanOrdering};
+use crate::reducer::{
+    append_winners, cast_arg, reduce_rowwise, rows, Fold, RowwiseReducer, Winner,
+};
 
 /// arguments validation to match behavior of greatest() in pyspark
 pub fn validate_args(args: &[ColumnarValue]) -> Result<()> {
-    if args.len() < 2 {
-        return Err(datafusion::error::DataFusionError::Execution(
-            "greatest() requires at least two argument".to_string(),
-        ));
+    validate_args_count("greatest", args.len())
+}
+
+/// arguments validation to match behavior of greatest() in pyspark
+pub fn validate_args_types(types: &[DataType]) -> Result<()> {
+    validate_types("greatest", types)
+}
+
+/// shared argument count check for greatest() and least(), `name` is used in the error message
+pub(crate) fn validate_args_count(name: &str, len: usize) -> Result<()> {
+    if len < 2 {
+        return Err(SparkError::wrong_num_args(name, "> 1", len).into());
     }
 
     Ok(())
 }
 
-/// arguments validation to match behavior of greatest() in pyspark
-pub fn validate_args_types(types: &[DataType]) -> Result<()> {
-    if types.len() < 2 {
-        return Err(datafusion::error::DataFusionError::Execution(
-            "greatest() requires at least two argument".to_string(),
-        ));
+/// shared argument types check for greatest() and least(), `name` is used in the error message.
+/// Arguments of different types are accepted if they can be widened to a common type.
+pub(crate) fn validate_types(name: &str, types: &[DataType]) -> Result<()> {
+    common_type(name, types, false).map(|_| ())
+}
+
+/// returns the type all arguments are cast to before greatest() or least() is evaluated,
+/// `ansi` selects the widening rules of `spark.sql.ansi.enabled=true`
+pub(crate) fn common_type(name: &str, types: &[DataType], ansi: bool) -> Result<DataType> {
+    common_type_of_args(name, &unknown_args(types.len()), types, ansi)
+}
+
+/// Casts the arguments to their Spark common type, so that their values can be compared.
+/// Unlike `common_type` it accepts a single argument, e.g. the only key of greatest_by().
+pub(crate) fn cast_to_common_type(
+    name: &str,
+    args: &[ColumnarValue],
+    ansi: bool,
+) -> Result<Vec<ColumnarValue>> {
+    let types = args.iter().map(|arg| arg.data_type()).collect::<Vec<_>>();
+    let common = spark_common_type(&types, ansi).ok_or_else(|| {
+        DataFusionError::from(SparkError::data_diff_types(
+            name,
+            &unknown_args(types.len()),
+            &types,
+        ))
+    })?;
+    args.iter().map(|arg| cast_arg(arg, &common)).collect()
+}
+
+/// `common_type` of arguments whose SQL is known, `args` render the call in the error message
+pub(crate) fn common_type_of_args(
+    name: &str,
+    args: &[String],
+    types: &[DataType],
+    ansi: bool,
+) -> Result<DataType> {
+    validate_args_count(name, types.len())?;
+    spark_common_type(types, ansi)
+        .ok_or_else(|| SparkError::data_diff_types(name, args, types).into())
+}
+
+/// How null arguments affect the result of greatest() and least()
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum NullHandling {
+    /// Spark semantics: null arguments are skipped, the result is null only if all arguments are null.
+    #[default]
+    Skip,
+    /// SQL standard semantics: the result is null if any of the arguments is null.
+    Propagate,
+}
+
+/// Which argument wins a row when several arguments are equal to the extremum
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum TieBreak {
+    /// the leftmost equal argument wins
+    #[default]
+    First,
+    /// the rightmost equal argument wins
+    Last,
+}
+
+/// Position of the first argument in the results of `greatest_index`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum IndexBase {
+    Zero,
+    /// as the positions in SQL (e.g. `array_position`)
+    #[default]
+    One,
+}
+
+/// Evaluation options shared by greatest() and least()
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub(crate) struct ExtremumOptions {
+    pub nan_ordering: NanOrdering,
+    pub null_handling: NullHandling,
+    pub collation: Collation,
+    pub ansi: bool,
+}
+
+/// Nullability of the result given nullability of the arguments.
+/// When nulls are skipped, one non-nullable argument is enough for the result to be non-nullable
+/// (as in Spark), when they are propagated, all arguments have to be non-nullable.
+pub(crate) fn result_nullable(
+    null_handling: NullHandling,
+    mut nullable: impl Iterator<Item = bool>,
+) -> bool {
+    match null_handling {
+        NullHandling::Skip => nullable.all(|nullable| nullable),
+        NullHandling::Propagate => nullable.any(|nullable| nullable),
     }
-    if types.windows(2).any(|w| w[0] != w[1]) {
-        return Err(datafusion::error::DataFusionError::Execution(
-            "greatest() requires all arguments to have the same type".to_string(),
-        ));
+}
+
+/// returns true if the not null `value` should replace the current `best` one
+fn replaces(
+    value: &ScalarValue,
+    best: &ScalarValue,
+    target: Ordering,
+    options: ExtremumOptions,
+    tie_break: TieBreak,
+) -> bool {
+    if best.is_null() {
+        return true;
     }
+    if options.nan_ordering == NanOrdering::IgnoreNan {
+        match (is_nan(value), is_nan(best)) {
+            (true, false) => return false,
+            (false, true) => return true,
+            _ => {}
+        }
+    }
+    match compare_collated(value, best, options.collation) {
+        Some(Ordering::Equal) => tie_break == TieBreak::Last,
+        ordering => ordering == Some(target),
+    }
+}
 
-    Ok(())
+/// The row loop of the generic algorithm: returns the position and the value of the argument
+/// with the extremum among `values`, or None if all of them are null.
+fn row_winner(
+    values: impl Iterator<Item = Result<ScalarValue>>,
+    target: Ordering,
+    options: ExtremumOptions,
+    tie_break: TieBreak,
+) -> Result<Option<(usize, ScalarValue)>> {
+    let mut best: Option<(usize, ScalarValue)> = None;
+    for (position, value) in values.enumerate() {
+        let value = value?;
+        if value.is_null() {
+            continue;
+        }
+        let replace = match &best {
+            Some((_, best)) => replaces(&value, best, target, options, tie_break),
+            None => true,
+        };
+        if replace {
+            best = Some((position, value));
+        }
+    }
+    Ok(best)
+}
+
+/// reduces literal arguments to a single scalar, nulls are skipped
+fn extremum_of_scalars<'a>(
+    scalars: impl IntoIterator<Item = &'a ScalarValue>,
+    target: Ordering,
+    options: ExtremumOptions,
+) -> Option<ScalarValue> {
+    let mut scalars = scalars.into_iter();
+    let mut best = scalars.next()?.clone();
+    for value in scalars {
+        if !value.is_null() && replaces(value, &best, target, options, TieBreak::First) {
+            best = value.clone();
+        }
+    }
+    Some(best)
 }
 
-/// algorithm of finding the greatest in each row with abstract appendable builder
-fn greatest_with_builder<T: AppendableBuilder>(
-    args: &[ArrayRef],
-    mut builder: T,
-) -> Result<ArrayRef> {
-    let rows = args[0].len();
+/// greatest() (`target` is `Ordering::Greater`) and least() (`Ordering::Less`) as a row-wise reduction,
+/// `target` is the ordering a new value must have against the current one to replace it.
+/// The generic loop takes the winning values, so consecutive rows won by the same argument are copied as one slice.
+pub(crate) struct Extremum<'a> {
+    pub name: &'a str,
+    pub target: Ordering,
+    pub options: ExtremumOptions,
+}
+
+impl RowwiseReducer for Extremum<'_> {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
+        common_type(self.name, arg_types, self.options.ansi)
+    }
+
+    fn fold(&self, acc: Option<&ScalarValue>, value: &ScalarValue) -> Result<Fold> {
+        Ok(match acc {
+            Some(best) if !replaces(value, best, self.target, self.options, TieBreak::First) => {
+                Fold::Keep
+            }
+            _ => Fold::Take,
+        })
+    }
 
-    for i in 0..rows {
-        let mut max = ScalarValue::Null;
+    /// Literal arguments are reduced to one scalar first. Columns of the same type are then compared
+    /// through the keys of a shared dictionary or with the kernels, the rest goes through the generic loop.
+    fn reduce_arrays(&self, args: &[ColumnarValue]) -> Result<Option<ColumnarValue>> {
+        let mut arrays = vec![];
+        let mut scalars = vec![];
         for arg in args {
-            let value = ScalarValue::try_from_array(arg, i)?;
-            if max.is_null() || max < value {
-                max = value;
+            match arg {
+                ColumnarValue::Array(array) => arrays.push(array.clone()),
+                ColumnarValue::Scalar(scalar) => scalars.push(scalar),
+            }
+        }
+
+        let scalar = extremum_of_scalars(scalars, self.target, self.options);
+        if arrays.is_empty() {
+            // there is at least one argument, so there is at least one scalar
+            return Ok(Some(ColumnarValue::Scalar(
+                scalar.unwrap_or(ScalarValue::Null),
+            )));
+        }
+        let scalar = scalar.filter(|scalar| !scalar.is_null());
+
+        let typ = arrays[0].data_type();
+        let same_types = arrays.iter().all(|arg| arg.data_type() == typ)
+            && scalar.as_ref().map_or(true, |s| &s.data_type() == typ);
+        if scalar.is_none() && same_types {
+            if let Some(result) =
+                extremum_with_shared_dictionary(&arrays, self.target, self.options)?
+            {
+                return Ok(Some(ColumnarValue::Array(result)));
             }
         }
-        builder.append_scalar_value(max)?;
+        if supports_kernels(typ) && same_types {
+            let result =
+                extremum_with_kernels(&arrays, scalar.as_ref(), self.target, self.options)?;
+            return Ok(Some(ColumnarValue::Array(result)));
+        }
+        Ok(None)
     }
+}
+
+/// User ordering of the rows of two arrays of the same type: compares the not null values
+/// `left[left_index]` and `right[right_index]`
+pub type RowComparator = Arc<dyn Fn(&ArrayRef, usize, &ArrayRef, usize) -> Ordering + Send + Sync>;
+
+/// The row-wise greatest value according to `comparator`, null arguments are skipped and the first of
+/// equal arguments wins. The arguments are cast to their common type first and literal arguments are passed
+/// to the comparator as single row arrays. The result is a scalar if all arguments are scalars.
+pub(crate) fn extremum_with_comparator(
+    name: &str,
+    args: &[ColumnarValue],
+    comparator: &RowComparator,
+) -> Result<ColumnarValue> {
+    validate_args_count(name, args.len())?;
+    let args = &cast_to_common_type(name, args, false)?;
+    let rows = rows(args);
+    // a literal is a single row array that is compared at index 0
+    let arrays = args
+        .iter()
+        .map(|arg| match arg {
+            ColumnarValue::Array(array) => Ok((array.clone(), false)),
+            ColumnarValue::Scalar(scalar) => Ok((scalar.to_array()?, true)),
+        })
+        .collect::<Result<Vec<_>>>()?;
 
-    Ok(builder.finish())
+    let winners = (0..rows).map(|i| {
+        let mut best: Option<(usize, usize)> = None;
+        for (arg, (array, literal)) in arrays.iter().enumerate() {
+            let index = if *literal { 0 } else { i };
+            if array.is_null(index) {
+                continue;
+            }
+            let replace = best.map_or(true, |(best, best_index)| {
+                comparator(array, index, &arrays[best].0, best_index) == Ordering::Greater
+            });
+            if replace {
+                best = Some((arg, index));
+            }
+        }
+        Ok(best.map_or(Winner::Null, |(arg, _)| Winner::Arg(arg)))
+    });
+    let mut builder = make_builder(arrays[0].0.data_type(), rows)?;
+    append_winners(&mut builder, winners, args)?;
+    let result = builder.finish();
+
+    if args
+        .iter()
+        .all(|arg| matches!(arg, ColumnarValue::Scalar(_)))
+    {
+        return Ok(ColumnarValue::Scalar(scalar_from_array(&result, 0)?));
+    }
+    Ok(ColumnarValue::Array(result))
+}
+
+/// Position of the argument with the extremum in each of the `rows` rows (from 0), None if all arguments are null.
+/// The arguments are cast to their common type first, values of different types are not comparable.
+/// Scalar arguments have the same value in every row, so only the position of the first row is computed
+/// if all arguments are scalars.
+pub(crate) fn arg_extremum(
+    name: &str,
+    args: &[ColumnarValue],
+    rows: usize,
+    target: Ordering,
+    options: ExtremumOptions,
+    tie_break: TieBreak,
+) -> Result<Vec<Option<usize>>> {
+    let args = &cast_to_common_type(name, args, options.ansi)?;
+    if args
+        .iter()
+        .all(|arg| matches!(arg, ColumnarValue::Scalar(_)))
+    {
+        let values = args.iter().map(|arg| match arg {
+            ColumnarValue::Scalar(scalar) => Ok(scalar.clone()),
+            ColumnarValue::Array(_) => unreachable!("all arguments are scalars"),
+        });
+        let winner = row_winner(values, target, options, tie_break)?;
+        return Ok(vec![winner.map(|(position, _)| position); rows]);
+    }
+    (0..rows)
+        .map(|i| {
+            let values = args.iter().map(|arg| match arg {
+                ColumnarValue::Array(array) => scalar_from_array(array, i),
+                ColumnarValue::Scalar(scalar) => Ok(scalar.clone()),
+            });
+            let winner = row_winner(values, target, options, tie_break)?;
+            Ok(winner.map(|(position, _)| position))
+        })
+        .collect()
+}
+
+/// Splits the arguments of greatest_by() into the values and the keys, there is one key for every value
+pub(crate) fn split_values_and_keys<'a, T>(
+    name: &str,
+    args: &'a [T],
+) -> Result<(&'a [T], &'a [T])> {
+    if args.is_empty() || args.len() % 2 != 0 {
+        return Err(
+            SparkError::wrong_num_args(name, "a positive even number of", args.len()).into(),
+        );
+    }
+    Ok(args.split_at(args.len() / 2))
 }
 
-/// greatest function implementation. Type validation is performed only when it runs as a scalar user-defined function.
+/// Returns the value whose key is the extremum of each row, null if all keys are null.
+/// The values are not compared, they are cast to their common type as the keys are cast to theirs.
+pub(crate) fn extremum_by(
+    name: &str,
+    args: &[ColumnarValue],
+    target: Ordering,
+    options: ExtremumOptions,
+    tie_break: TieBreak,
+) -> Result<ColumnarValue> {
+    let (values, keys) = split_values_and_keys(name, args)?;
+    // literal keys of column values are the same in every row of the values
+    let positions = arg_extremum(name, keys, rows(args), target, options, tie_break)?;
+    let values = &cast_to_common_type(name, values, options.ansi)?;
+    let data_type = values[0].data_type();
+
+    if args
+        .iter()
+        .all(|arg| matches!(arg, ColumnarValue::Scalar(_)))
+    {
+        return Ok(ColumnarValue::Scalar(match positions[0] {
+            Some(position) => match &values[position] {
+                ColumnarValue::Scalar(value) => value.clone(),
+                ColumnarValue::Array(_) => unreachable!("all arguments are scalars"),
+            },
+            None => ScalarValue::try_from(&data_type)?,
+        }));
+    }
+
+    let rows = positions.len();
+    let mut sources = values
+        .iter()
+        .map(|value| value.clone().into_array(rows))
+        .collect::<Result<Vec<_>>>()?;
+    // rows without a winner take the only value of a null array
+    sources.push(new_null_array(&data_type, 1));
+    let indices = positions
+        .iter()
+        .enumerate()
+        .map(|(row, position)| match position {
+            Some(position) => (*position, row),
+            None => (values.len(), 0),
+        })
+        .collect::<Vec<_>>();
+    let sources = sources
+        .iter()
+        .map(|array| array.as_ref())
+        .collect::<Vec<_>>();
+    Ok(ColumnarValue::Array(interleave(&sources, &indices)?))
+}
+
+/// Computes the row-wise extremum, literal arguments are never expanded to arrays.
+/// The result is a scalar if all arguments are scalars.
+pub(crate) fn extremum(
+    name: &str,
+    args: &[ColumnarValue],
+    target: Ordering,
+    options: ExtremumOptions,
+) -> Result<ColumnarValue> {
+    let result = extremum_skipping_nulls(name, args, target, options)?;
+    match options.null_handling {
+        NullHandling::Skip => Ok(result),
+        NullHandling::Propagate => propagate_nulls(args, result),
+    }
+}
+
+/// makes the result null in every row where any of the arguments is null
+fn propagate_nulls(args: &[ColumnarValue], result: ColumnarValue) -> Result<ColumnarValue> {
+    let null_scalar = args
+        .iter()
+        .any(|arg| matches!(arg, ColumnarValue::Scalar(scalar) if scalar.is_null()));
+
+    let result = match result {
+        ColumnarValue::Scalar(scalar) if null_scalar => {
+            return Ok(ColumnarValue::Scalar(ScalarValue::try_from(
+                scalar.data_type(),
+            )?))
+        }
+        ColumnarValue::Scalar(scalar) => return Ok(ColumnarValue::Scalar(scalar)),
+        ColumnarValue::Array(array) if null_scalar => {
+            return Ok(ColumnarValue::Array(new_null_array(
+                array.data_type(),
+                array.len(),
+            )))
+        }
+        ColumnarValue::Array(array) => array,
+    };
+
+    let mut any_null: Option<BooleanArray> = None;
+    for arg in args {
+        if let ColumnarValue::Array(array) = arg {
+            if array.null_count() == 0 {
+                continue;
+            }
+            let nulls = is_null(array)?;
+            any_null = Some(match any_null {
+                Some(any_null) => or(&any_null, &nulls)?,
+                None => nulls,
+            });
+        }
+    }
+
+    Ok(ColumnarValue::Array(match any_null {
+        Some(any_null) => nullif(&result, &any_null)?,
+        None => result,
+    }))
+}
+
+/// Spark's row-wise extremum where nulls are skipped
+fn extremum_skipping_nulls(
+    name: &str,
+    args: &[ColumnarValue],
+    target: Ordering,
+    options: ExtremumOptions,
+) -> Result<ColumnarValue> {
+    validate_args_count(name, args.len())?;
+    reduce_rowwise(
+        &Extremum {
+            name,
+            target,
+            options,
+        },
+        args,
+    )
+}
+
+/// greatest function implementation. The arguments are validated and cast to their common type by `reduce_rowwise`
+/// with the `Extremum` reducer, as in the scalar user-defined function: arguments without a common type fail
+/// with a `SparkError`.
 pub fn greatest(args: &[ColumnarValue]) -> Result<ArrayRef> {
-    validate_args(args)?;
-    let args = ColumnarValue::values_to_arrays(args)?;
-    let typ = args[0].data_type();
-    let builder = make_builder(typ, args[0].len())?;
-    greatest_with_builder(&args, builder)
+    extremum(
+        "greatest",
+        args,
+        Ordering::Greater,
+        ExtremumOptions::default(),
+    )?
+    .into_array(1)
+}
+
+/// least function implementation. The arguments are validated and cast to their common type by `reduce_rowwise`
+/// with the `Extremum` reducer, as in the scalar user-defined function: arguments without a common type fail
+/// with a `SparkError`.
+pub fn least(args: &[ColumnarValue]) -> Result<ArrayRef> {
+    extremum("least", args, Ordering::Less, ExtremumOptions::default())?.into_array(1)
 }
@@ -0,0 +1,198 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use datafusion::arrow::array::{
+    Array, ArrayRef, AsArray, BooleanArray, Datum, GenericStringArray, OffsetSizeTrait,
+    PrimitiveArray, Scalar, StringViewArray,
+};
+use datafusion::arrow::compute::kernels::cmp::{gt, lt};
+use datafusion::arrow::compute::kernels::zip::zip;
+use datafusion::arrow::compute::{and, and_not, is_not_null, is_null, or, prep_null_mask_filter};
+use datafusion::arrow::datatypes::{DataType, Float16Type, Float32Type, Float64Type};
+use datafusion::error::Result;
+use datafusion::scalar::ScalarValue;
+
+use crate::greatest::ExtremumOptions;
+use crate::ordering::{Collation, NanOrdering, SparkFloat};
+
+/// Returns true if the columns of type `datatype` can be folded with the arrow comparison kernels.
+/// Nested types are not supported by arrow `cmp` and go through the generic builder loop.
+pub(crate) fn supports_kernels(datatype: &DataType) -> bool {
+    datatype.is_primitive()
+        || matches!(
+            datatype,
+            DataType::Boolean
+                | DataType::Utf8
+                | DataType::LargeUtf8
+                | DataType::Utf8View
+                | DataType::Binary
+                | DataType::LargeBinary
+                | DataType::BinaryView
+                | DataType::FixedSizeBinary(_)
+        )
+}
+
+/// Vectorized version of the row-wise extremum. Columns are folded pairwise: on each step
+/// the accumulator takes the value of the next column wherever that value is not null and
+/// either the accumulator is null or the value compares as `target` against it.
+/// Null bitmaps are merged by the `zip` kernel, so a row is null only if all columns are null.
+///
+/// `scalar` is an already reduced literal argument, it is compared without being expanded to an array.
+pub(crate) fn extremum_with_kernels(
+    args: &[ArrayRef],
+    scalar: Option<&ScalarValue>,
+    target: Ordering,
+    options: ExtremumOptions,
+) -> Result<ArrayRef> {
+    let mut acc = args[0].clone();
+
+    for arg in &args[1..] {
+        acc = fold_step(&acc, arg, target, options)?;
+    }
+
+    if let Some(scalar) = scalar.filter(|scalar| !scalar.is_null()) {
+        acc = fold_step(&acc, &scalar.to_scalar()?, target, options)?;
+    }
+
+    Ok(acc)
+}
+
+/// merges `value` (an array or a scalar) into the accumulator
+fn fold_step(
+    acc: &ArrayRef,
+    value: &dyn Datum,
+    target: Ordering,
+    options: ExtremumOptions,
+) -> Result<ArrayRef> {
+    let (value_array, is_scalar) = value.get();
+    if is_scalar && value_array.is_null(0) {
+        return Ok(acc.clone());
+    }
+
+    let better = match acc.data_type() {
+        DataType::Float16 => float_better::<Float16Type>(acc, value, target, options)?,
+        DataType::Float32 => float_better::<Float32Type>(acc, value, target, options)?,
+        DataType::Float64 => float_better::<Float64Type>(acc, value, target, options)?,
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View
+            if options.collation != Collation::Binary =>
+        {
+            string_better(acc, value, target, options.collation)?
+        }
+        _ => null_as_false(compare(value, acc, target)?),
+    };
+    let mut replace = or(&is_null(acc)?, &better)?;
+    if !is_scalar {
+        replace = and(&is_not_null(value_array)?, &replace)?;
+    }
+
+    Ok(zip(&replace, value, acc)?)
+}
+
+/// row-wise `value` compares as `target` against `acc`
+fn compare(value: &dyn Datum, acc: &dyn Datum, target: Ordering) -> Result<BooleanArray> {
+    Ok(match target {
+        Ordering::Greater => gt(value, acc)?,
+        Ordering::Less => lt(value, acc)?,
+        Ordering::Equal => {
+            return Err(datafusion::error::DataFusionError::Internal(
+                "extremum can't be computed for Ordering::Equal".to_string(),
+            ))
+        }
+    })
+}
+
+/// Rows where the floating point `value` should replace not null `acc` according to the NaN ordering.
+/// Arrow kernels compare floats with `total_cmp`, so both sides are normalized first to get
+/// the Spark ordering, the original values are still the ones that end up in the result.
+fn float_better<T: SparkFloat>(
+    acc: &ArrayRef,
+    value: &dyn Datum,
+    target: Ordering,
+    options: ExtremumOptions,
+) -> Result<BooleanArray> {
+    let (value_array, is_scalar) = value.get();
+    let acc = acc.as_primitive::<T>();
+    let value_array = value_array.as_primitive::<T>();
+
+    let acc_keys: PrimitiveArray<T> = acc.unary(T::spark_normalize);
+    let value_keys: PrimitiveArray<T> = value_array.unary(T::spark_normalize);
+    let better = if is_scalar {
+        compare(&Scalar::new(value_keys), &acc_keys, target)?
+    } else {
+        compare(&value_keys, &acc_keys, target)?
+    };
+    let better = null_as_false(better);
+
+    match options.nan_ordering {
+        NanOrdering::Spark => Ok(better),
+        NanOrdering::IgnoreNan => {
+            // NaN loses against any number and any number wins against NaN
+            let acc_nan = null_as_false(BooleanArray::from_unary(acc, T::is_nan));
+            let better = or(&acc_nan, &better)?;
+            if is_scalar {
+                return Ok(if T::is_nan(value_array.value(0)) {
+                    BooleanArray::from(vec![false; acc.len()])
+                } else {
+                    better
+                });
+            }
+            let value_nan = null_as_false(BooleanArray::from_unary(value_array, T::is_nan));
+            Ok(and_not(&better, &value_nan)?)
+        }
+    }
+}
+
+/// Rows where the string `value` should replace `acc` under a non-binary collation.
+/// As with the floats, the kernels compare the collation keys of both sides and the original strings
+/// end up in the result.
+fn string_better(
+    acc: &ArrayRef,
+    value: &dyn Datum,
+    target: Ordering,
+    collation: Collation,
+) -> Result<BooleanArray> {
+    let (value_array, is_scalar) = value.get();
+    let acc_keys = collation_keys(acc.as_ref(), collation);
+    let value_keys = collation_keys(value_array, collation);
+    let better = if is_scalar {
+        compare(&Scalar::new(value_keys), &acc_keys, target)?
+    } else {
+        compare(&value_keys, &acc_keys, target)?
+    };
+    Ok(null_as_false(better))
+}
+
+fn collation_keys(array: &dyn Array, collation: Collation) -> ArrayRef {
+    match array.data_type() {
+        DataType::LargeUtf8 => Arc::new(offset_collation_keys::<i64>(array, collation)),
+        DataType::Utf8View => Arc::new(
+            array
+                .as_string_view()
+                .iter()
+                .map(|value| value.map(|value| collation.key(value)))
+                .collect::<StringViewArray>(),
+        ),
+        _ => Arc::new(offset_collation_keys::<i32>(array, collation)),
+    }
+}
+
+/// collation keys keep the offset width of the strings, so large strings don't overflow i32 offsets
+fn offset_collation_keys<O: OffsetSizeTrait>(
+    array: &dyn Array,
+    collation: Collation,
+) -> GenericStringArray<O> {
+    array
+        .as_string::<O>()
+        .iter()
+        .map(|value| value.map(|value| collation.key(value)))
+        .collect()
+}
+
+/// comparison result is null if any side is null, such rows are treated as false
+fn null_as_false(mask: BooleanArray) -> BooleanArray {
+    if mask.null_count() > 0 {
+        prep_null_mask_filter(&mask)
+    } else {
+        mask
+    }
+}
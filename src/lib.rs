@@ -1,30 +1,71 @@
+mod bounds;
 mod builder;
+mod coercion;
+mod dictionary;
+mod error;
 mod greatest;
+mod kernels;
+mod ordering;
+mod reducer;
 mod udf;
 
 pub use builder::make_builder;
+pub use builder::make_builder_for_field;
 pub use builder::AppendableBuilder;
+pub use builder::FieldBuilder;
+pub use builder::TypeMismatch;
+pub use builder::TypedBuilder;
+pub use coercion::spark_common_type;
+pub use error::SparkError;
 pub use greatest::greatest;
+pub use greatest::least;
 pub use greatest::validate_args;
 pub use greatest::validate_args_types;
+pub use greatest::IndexBase;
+pub use greatest::NullHandling;
+pub use greatest::RowComparator;
+pub use greatest::TieBreak;
+pub use ordering::Collation;
+pub use ordering::NanOrdering;
+pub use reducer::reduce_rowwise;
+pub use reducer::Fold;
+pub use reducer::RowwiseReducer;
+pub use udf::ExtremumKind;
+pub use udf::ExtremumUDF;
+pub use udf::Greatest;
+pub use udf::GreatestByUDF;
+pub use udf::GreatestIndexUDF;
 pub use udf::GreatestUDF;
+pub use udf::Least;
+pub use udf::LeastUDF;
+pub use udf::RowwiseExtremumUDF;
+pub use udf::RowwiseReducerUDF;
 
+#[cfg(test)]
 mod tests {
     use std::sync::Arc;
 
     use datafusion::arrow::array::{
-        ArrayBuilder, ArrayRef, Float64Array, GenericListBuilder, Int64Array, Int64Builder,
+        Array, ArrayBuilder, ArrayRef, Decimal128Array, Float16Array, Float32Array, Float64Array,
+        GenericListBuilder, Int64Array, Int64Builder, StringArray, TimestampMillisecondArray,
     };
-    use datafusion::arrow::datatypes::ArrowNativeType;
-    use datafusion::arrow::ipc::ListBuilder;
+    use datafusion::arrow::datatypes::DataType;
     use datafusion::error::{DataFusionError, Result};
 
-    use datafusion::logical_expr::{ColumnarValue, ScalarUDF};
+    use datafusion::logical_expr::{ColumnarValue, ScalarUDF, ScalarUDFImpl};
     use datafusion::prelude::*;
-    use udf::GreatestUDF;
+    use datafusion::scalar::ScalarValue;
+    use udf::{GreatestUDF, LeastUDF};
 
     use super::*;
 
+    fn spark_error(error: DataFusionError) -> SparkError {
+        match error {
+            DataFusionError::External(error) => error.downcast_ref::<SparkError>().unwrap().clone(),
+            error => panic!("expected a Spark error, got {error}"),
+        }
+    }
+
     fn create_array(data: Vec<Option<i64>>) -> ArrayRef {
         Arc::new(Int64Array::from(data))
     }
@@ -96,15 +137,10 @@ mod tests {
         assert!(result.is_err());
         let result = result.unwrap_err();
 
-        assert!(matches!(result, DataFusionError::Execution(_)));
-
-        let DataFusionError::Execution(msg) = result else {
-            unreachable!();
-        };
-
-        let answer = "greatest() requires at least two argument".to_string();
-
-        assert_eq!(msg, answer);
+        let error = spark_error(result);
+        assert_eq!(error.error_class(), "WRONG_NUM_ARGS.WITHOUT_SUGGESTION");
+        assert_eq!(error.parameter("functionName"), Some("`greatest`"));
+        assert_eq!(error.parameter("expectedNum"), Some("> 1"));
     }
 
     #[test]
@@ -113,15 +149,10 @@ mod tests {
         assert!(result.is_err());
         let result = result.unwrap_err();
 
-        assert!(matches!(result, DataFusionError::Execution(_)));
-
-        let DataFusionError::Execution(msg) = result else {
-            unreachable!();
-        };
-
-        let answer = "greatest() requires at least two argument".to_string();
-
-        assert_eq!(msg, answer);
+        let error = spark_error(result);
+        assert_eq!(error.error_class(), "WRONG_NUM_ARGS.WITHOUT_SUGGESTION");
+        assert_eq!(error.parameter("functionName"), Some("`greatest`"));
+        assert_eq!(error.parameter("expectedNum"), Some("> 1"));
     }
 
     #[test]
@@ -210,7 +241,7 @@ mod tests {
     async fn test_greatest_udf() -> Result<()> {
         let ctx = SessionContext::new();
 
-        let greatest_udf = ScalarUDF::from(GreatestUDF);
+        let greatest_udf = ScalarUDF::from(GreatestUDF::new());
 
         ctx.register_udf(greatest_udf.clone());
 
@@ -232,10 +263,10 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_greatest_type_validation() -> Result<()> {
+    async fn test_greatest_type_coercion() -> Result<()> {
         let ctx = SessionContext::new();
 
-        let greatest_udf = ScalarUDF::from(GreatestUDF);
+        let greatest_udf = ScalarUDF::from(GreatestUDF::new());
 
         ctx.register_udf(greatest_udf.clone());
 
@@ -245,20 +276,3087 @@ mod tests {
 
         let result = df.select(vec![greatest_udf
             .call(vec![col("a"), col("b"), col("c")])
+            .alias("greatest")])?;
+
+        let result = result.collect().await?;
+        let result = result[0].columns()[0].clone();
+
+        let answer = Arc::new(Float64Array::from(vec![1.0, 3.0, 4.0])) as ArrayRef;
+        assert_eq!(&result, &answer);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_least_basics() {
+        let input = create_column_values(vec![
+            vec![Some(1), Some(4), Some(7)],
+            vec![Some(2), Some(5), Some(8)],
+            vec![Some(3), Some(6), Some(9)],
+        ]);
+
+        let result = least(&input);
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        let answer = create_array(vec![Some(1), Some(4), Some(7)]);
+
+        assert_eq!(&result, &answer);
+    }
+
+    #[test]
+    fn test_least_nulls() {
+        let input = create_column_values(vec![
+            vec![None, Some(4), None],
+            vec![Some(2), None, None],
+            vec![Some(3), Some(6), None],
+        ]);
+
+        let result = least(&input);
+        assert!(result.is_ok());
+        let result = result.unwrap();
+
+        let answer = create_array(vec![Some(2), Some(4), None]);
+
+        assert_eq!(&result, &answer);
+    }
+
+    #[test]
+    fn test_least_args_validation() {
+        let input = create_column_values(vec![vec![Some(1), Some(4), Some(7)]]);
+
+        let result = least(&input);
+        assert!(result.is_err());
+        let result = result.unwrap_err();
+
+        let error = spark_error(result);
+        assert_eq!(error.error_class(), "WRONG_NUM_ARGS.WITHOUT_SUGGESTION");
+        assert_eq!(error.parameter("functionName"), Some("`least`"));
+        assert_eq!(error.parameter("expectedNum"), Some("> 1"));
+    }
+
+    #[tokio::test]
+    async fn test_least_udf() -> Result<()> {
+        let ctx = SessionContext::new();
+
+        let least_udf = ScalarUDF::from(LeastUDF::new());
+
+        ctx.register_udf(least_udf.clone());
+
+        let df = ctx
+            .read_csv("tests/data/data.csv", CsvReadOptions::new())
+            .await?;
+
+        let result = df.select(vec![least_udf
+            .call(vec![col("a"), col("b"), col("c")])
+            .alias("least")])?;
+
+        let result = result.collect().await?;
+        let result = result[0].columns()[0].clone();
+
+        let answer = create_array(vec![Some(1), Some(4), Some(7)]);
+        assert_eq!(&result, &answer);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_least_type_coercion() -> Result<()> {
+        let ctx = SessionContext::new();
+
+        let least_udf = ScalarUDF::from(LeastUDF::new());
+
+        ctx.register_udf(least_udf.clone());
+
+        let df = ctx
+            .read_csv("tests/data/types.csv", CsvReadOptions::new())
+            .await?;
+
+        let result = df.select(vec![least_udf
+            .call(vec![col("a"), col("b"), col("c")])
+            .alias("least")])?;
+
+        let result = result.collect().await?;
+        let result = result[0].columns()[0].clone();
+
+        let answer = Arc::new(Float64Array::from(vec![1.0, 2.0, 3.0])) as ArrayRef;
+        assert_eq!(&result, &answer);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_greatest_strings() {
+        let input = vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![
+                Some("apple"),
+                None,
+                Some("pear"),
+                None,
+            ])) as ArrayRef),
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![
+                Some("banana"),
+                Some("cherry"),
+                Some("peach"),
+                None,
+            ])) as ArrayRef),
+        ];
+
+        let result = greatest(&input).unwrap();
+        let answer = Arc::new(StringArray::from(vec![
+            Some("banana"),
+            Some("cherry"),
+            Some("pear"),
+            None,
+        ])) as ArrayRef;
+        assert_eq!(&result, &answer);
+
+        let result = least(&input).unwrap();
+        let answer = Arc::new(StringArray::from(vec![
+            Some("apple"),
+            Some("cherry"),
+            Some("peach"),
+            None,
+        ])) as ArrayRef;
+        assert_eq!(&result, &answer);
+    }
+
+    #[test]
+    fn test_greatest_decimals() {
+        let decimals = |data: Vec<Option<i128>>| {
+            Arc::new(
+                Decimal128Array::from(data)
+                    .with_precision_and_scale(10, 2)
+                    .unwrap(),
+            ) as ArrayRef
+        };
+        let input = vec![
+            ColumnarValue::Array(decimals(vec![Some(100), None, Some(-5)])),
+            ColumnarValue::Array(decimals(vec![Some(99), Some(1), Some(-7)])),
+        ];
+
+        let result = greatest(&input).unwrap();
+        let answer = decimals(vec![Some(100), Some(1), Some(-5)]);
+
+        assert_eq!(&result, &answer);
+    }
+
+    #[test]
+    fn test_greatest_timestamps_keep_timezone() {
+        let timestamps = |data: Vec<Option<i64>>| {
+            Arc::new(TimestampMillisecondArray::from(data).with_timezone("+01:00")) as ArrayRef
+        };
+        let input = vec![
+            ColumnarValue::Array(timestamps(vec![Some(1000), None])),
+            ColumnarValue::Array(timestamps(vec![Some(2000), None])),
+            ColumnarValue::Array(timestamps(vec![Some(1500), Some(10)])),
+        ];
+
+        let result = greatest(&input).unwrap();
+        let answer = timestamps(vec![Some(2000), Some(10)]);
+
+        assert_eq!(&result, &answer);
+    }
+
+    #[test]
+    fn test_greatest_array_and_scalar() {
+        let input = vec![
+            ColumnarValue::Array(create_array(vec![Some(-1), None, Some(7)])),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(0))),
+        ];
+
+        let result = GreatestUDF::new().invoke(&input).unwrap();
+        let ColumnarValue::Array(result) = result else {
+            panic!("expected an array result");
+        };
+
+        let answer = create_array(vec![Some(0), Some(0), Some(7)]);
+        assert_eq!(&result, &answer);
+
+        let result = LeastUDF::new().invoke(&input).unwrap();
+        let ColumnarValue::Array(result) = result else {
+            panic!("expected an array result");
+        };
+
+        let answer = create_array(vec![Some(-1), Some(0), Some(0)]);
+        assert_eq!(&result, &answer);
+    }
+
+    #[test]
+    fn test_greatest_array_and_null_scalar() {
+        let input = vec![
+            ColumnarValue::Scalar(ScalarValue::Int64(None)),
+            ColumnarValue::Array(create_array(vec![Some(-1), None, Some(7)])),
+        ];
+
+        let result = GreatestUDF::new().invoke(&input).unwrap();
+        let ColumnarValue::Array(result) = result else {
+            panic!("expected an array result");
+        };
+
+        let answer = create_array(vec![Some(-1), None, Some(7)]);
+        assert_eq!(&result, &answer);
+    }
+
+    #[test]
+    fn test_greatest_scalars() {
+        let input = vec![
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(1))),
+            ColumnarValue::Scalar(ScalarValue::Int64(None)),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(3))),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(2))),
+        ];
+
+        let result = GreatestUDF::new().invoke(&input).unwrap();
+        let ColumnarValue::Scalar(result) = result else {
+            panic!("expected a scalar result");
+        };
+        assert_eq!(result, ScalarValue::Int64(Some(3)));
+
+        let result = LeastUDF::new().invoke(&input).unwrap();
+        let ColumnarValue::Scalar(result) = result else {
+            panic!("expected a scalar result");
+        };
+        assert_eq!(result, ScalarValue::Int64(Some(1)));
+    }
+
+    #[test]
+    fn test_greatest_null_scalars() {
+        let input = vec![
+            ColumnarValue::Scalar(ScalarValue::Int64(None)),
+            ColumnarValue::Scalar(ScalarValue::Int64(None)),
+        ];
+
+        let result = GreatestUDF::new().invoke(&input).unwrap();
+        let ColumnarValue::Scalar(result) = result else {
+            panic!("expected a scalar result");
+        };
+        assert_eq!(result, ScalarValue::Int64(None));
+    }
+
+    #[test]
+    fn test_greatest_list_array_and_scalar() {
+        let mut builder = GenericListBuilder::<i32, _>::new(Int64Builder::new());
+        builder.values().append_value(1);
+        builder.append(true);
+        builder.values().append_value(3);
+        builder.append(true);
+        builder.append(false);
+        let column = Arc::new(builder.finish()) as ArrayRef;
+
+        let scalar = ScalarValue::List(Arc::new(
+            datafusion::arrow::array::ListArray::from_iter_primitive::<
+                datafusion::arrow::datatypes::Int64Type,
+                _,
+                _,
+            >(vec![Some(vec![Some(2)])]),
+        ));
+
+        let input = vec![ColumnarValue::Array(column), ColumnarValue::Scalar(scalar)];
+        let result = greatest(&input).unwrap();
+
+        let mut answer_builder = GenericListBuilder::<i32, _>::new(Int64Builder::new());
+        answer_builder.values().append_value(2);
+        answer_builder.append(true);
+        answer_builder.values().append_value(3);
+        answer_builder.append(true);
+        answer_builder.values().append_value(2);
+        answer_builder.append(true);
+
+        let answer = Arc::new(answer_builder.finish()) as ArrayRef;
+        assert_eq!(&result, &answer);
+    }
+
+    #[tokio::test]
+    async fn test_greatest_type_validation() -> Result<()> {
+        let ctx = SessionContext::new();
+
+        let greatest_udf = ScalarUDF::from(GreatestUDF::new());
+
+        ctx.register_udf(greatest_udf.clone());
+
+        let df = ctx
+            .read_csv("tests/data/incompatible.csv", CsvReadOptions::new())
+            .await?;
+
+        let result = df.select(vec![greatest_udf
+            .call(vec![col("a"), col("b")])
             .alias("greatest")]);
 
         assert!(result.is_err());
         let result = result.unwrap_err();
 
-        assert!(matches!(result, DataFusionError::Execution(_)));
-        let DataFusionError::Execution(msg) = result else {
-            unreachable!();
+        let error = spark_error(result);
+        assert_eq!(error.error_class(), "DATATYPE_MISMATCH.DATA_DIFF_TYPES");
+        assert_eq!(error.sql_state(), "42K09");
+        assert_eq!(error.parameter("functionName"), Some("`greatest`"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_greatest_sql_literal_coercion() -> Result<()> {
+        let ctx = SessionContext::new();
+        ctx.register_udf(ScalarUDF::from(GreatestUDF::new()));
+        ctx.register_csv("data", "tests/data/data.csv", CsvReadOptions::new())
+            .await?;
+
+        let result = ctx
+            .sql("SELECT greatest(a, 2.5) AS greatest FROM data")
+            .await?
+            .collect()
+            .await?;
+        let result = result[0].columns()[0].clone();
+
+        let answer = Arc::new(Float64Array::from(vec![2.5, 4.0, 7.0])) as ArrayRef;
+        assert_eq!(&result, &answer);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_greatest_mixed_scalars() {
+        let args = [
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(1))),
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(2.5))),
+        ];
+        // literals are cast to the common type before they are compared, as columns are
+        let ColumnarValue::Scalar(result) = GreatestUDF::new().invoke(&args).unwrap() else {
+            panic!("expected a scalar result");
+        };
+        assert_eq!(result, ScalarValue::Float64(Some(2.5)));
+        let ColumnarValue::Scalar(result) = LeastUDF::new().invoke(&args).unwrap() else {
+            panic!("expected a scalar result");
         };
+        assert_eq!(result, ScalarValue::Float64(Some(1.0)));
+
+        let arrays = args
+            .iter()
+            .map(|arg| ColumnarValue::Array(arg.clone().into_array(1).unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            greatest(&arrays).unwrap().as_ref(),
+            &Float64Array::from(vec![2.5]) as &dyn Array
+        );
+    }
+
+    #[test]
+    fn test_spark_common_type() {
+        use datafusion::arrow::datatypes::{DataType, TimeUnit};
+
+        assert_eq!(
+            spark_common_type(&[DataType::Utf8View, DataType::LargeUtf8], false),
+            Some(DataType::Utf8View)
+        );
+        assert_eq!(
+            spark_common_type(&[DataType::Binary, DataType::BinaryView], false),
+            Some(DataType::BinaryView)
+        );
+
+        let dictionary = DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+        assert_eq!(
+            spark_common_type(&[dictionary.clone(), dictionary.clone()], false),
+            Some(dictionary.clone())
+        );
+        assert_eq!(
+            spark_common_type(&[dictionary.clone(), DataType::LargeUtf8], false),
+            Some(DataType::LargeUtf8)
+        );
+
+        assert_eq!(
+            spark_common_type(&[DataType::Int32, DataType::Float64], false),
+            Some(DataType::Float64)
+        );
+        assert_eq!(
+            spark_common_type(&[DataType::Int8, DataType::UInt8], false),
+            Some(DataType::Int16)
+        );
+        assert_eq!(
+            spark_common_type(&[DataType::Int32, DataType::Decimal128(5, 2)], false),
+            Some(DataType::Decimal128(12, 2))
+        );
+        assert_eq!(
+            spark_common_type(
+                &[DataType::Decimal128(10, 4), DataType::Decimal128(20, 0)],
+                false
+            ),
+            Some(DataType::Decimal128(24, 4))
+        );
+        assert_eq!(
+            spark_common_type(&[DataType::Decimal128(10, 2), DataType::Float32], false),
+            Some(DataType::Float64)
+        );
+        assert_eq!(
+            spark_common_type(
+                &[
+                    DataType::Date32,
+                    DataType::Timestamp(TimeUnit::Microsecond, None)
+                ],
+                false
+            ),
+            Some(DataType::Timestamp(TimeUnit::Microsecond, None))
+        );
+        assert_eq!(
+            spark_common_type(&[DataType::Null, DataType::Utf8], false),
+            Some(DataType::Utf8)
+        );
+        assert_eq!(
+            spark_common_type(&[DataType::Utf8, DataType::Int32], false),
+            None
+        );
+        assert_eq!(
+            spark_common_type(&[DataType::Utf8, DataType::Int32], true),
+            Some(DataType::Int64)
+        );
+        assert_eq!(
+            spark_common_type(&[DataType::Utf8, DataType::Date32], true),
+            Some(DataType::Date32)
+        );
+        assert_eq!(
+            spark_common_type(&[DataType::Utf8, DataType::Decimal128(10, 2)], true),
+            Some(DataType::Float64)
+        );
+        assert_eq!(
+            spark_common_type(&[DataType::Boolean, DataType::Int32], true),
+            None
+        );
+        assert_eq!(
+            spark_common_type(&[DataType::Int64, DataType::Float16], false),
+            Some(DataType::Float64)
+        );
+        assert_eq!(
+            spark_common_type(&[DataType::Float16, DataType::Float32], false),
+            Some(DataType::Float32)
+        );
+    }
 
-        let answer = "greatest() requires all arguments to have the same type".to_string();
+    #[tokio::test]
+    async fn test_greatest_sql_ansi_coercion() -> Result<()> {
+        let ctx = SessionContext::new();
+        ctx.register_udf(ScalarUDF::from(GreatestUDF::new().with_ansi(true)));
+        ctx.register_udf(ScalarUDF::from(LeastUDF::new()));
+        ctx.register_csv("data", "tests/data/data.csv", CsvReadOptions::new())
+            .await?;
 
-        assert_eq!(&msg, &answer);
+        let result = ctx
+            .sql("SELECT greatest(a, '5') AS greatest FROM data")
+            .await?
+            .collect()
+            .await?;
+        let result = result[0].columns()[0].clone();
+        assert_eq!(result.data_type(), &DataType::Int64);
+        assert_eq!(&result, &create_array(vec![Some(5), Some(5), Some(7)]));
+
+        // strings are not promoted without ANSI mode
+        let error = ctx
+            .sql("SELECT least(a, '5') AS least FROM data")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            spark_error(error).error_class(),
+            "DATATYPE_MISMATCH.DATA_DIFF_TYPES"
+        );
 
         Ok(())
     }
+
+    macro_rules! test_nan_ordering {
+        ($name:ident, $array:ty, $scalar:ident, $from:expr) => {
+            #[test]
+            fn $name() {
+                let from = $from;
+                let nan = from(f64::NAN);
+                let column = |data: Vec<Option<f64>>| {
+                    Arc::new(<$array>::from_iter(
+                        data.into_iter().map(|value| value.map(from)),
+                    )) as ArrayRef
+                };
+                let input = vec![
+                    ColumnarValue::Array(column(vec![
+                        Some(1.0),
+                        Some(f64::NAN),
+                        Some(-0.0),
+                        Some(f64::NEG_INFINITY),
+                        Some(-f64::NAN),
+                    ])),
+                    ColumnarValue::Array(column(vec![
+                        Some(f64::NAN),
+                        None,
+                        Some(0.0),
+                        Some(-f64::NAN),
+                        Some(2.0),
+                    ])),
+                ];
+
+                let evaluate = |udf: &dyn ScalarUDFImpl| {
+                    let ColumnarValue::Array(result) = udf.invoke(&input).unwrap() else {
+                        panic!("expected an array result");
+                    };
+                    let result = result.as_any().downcast_ref::<$array>().unwrap().clone();
+                    result.iter().map(|v| v.unwrap()).collect::<Vec<_>>()
+                };
+
+                // NaN is the largest value, -0.0 == 0.0 so the first one is kept
+                let result = evaluate(&GreatestUDF::new());
+                assert!(result[0].is_nan());
+                assert!(result[1].is_nan());
+                assert!(result[2] == from(0.0) && result[2].is_sign_negative());
+                assert!(result[3].is_nan());
+                assert!(result[4].is_nan());
+
+                let result = evaluate(&LeastUDF::new());
+                assert_eq!(result[0], from(1.0));
+                assert!(result[1].is_nan());
+                assert!(result[2].is_sign_negative());
+                assert_eq!(result[3], from(f64::NEG_INFINITY));
+                assert_eq!(result[4], from(2.0));
+
+                // NaN is skipped unless there is nothing else
+                let result =
+                    evaluate(&GreatestUDF::new().with_nan_ordering(NanOrdering::IgnoreNan));
+                assert_eq!(result[0], from(1.0));
+                assert!(result[1].is_nan());
+                assert!(result[2].is_sign_negative());
+                assert_eq!(result[3], from(f64::NEG_INFINITY));
+                assert_eq!(result[4], from(2.0));
+
+                let result = evaluate(&LeastUDF::new().with_nan_ordering(NanOrdering::IgnoreNan));
+                assert_eq!(result[0], from(1.0));
+                assert!(result[1].is_nan());
+                assert_eq!(result[3], from(f64::NEG_INFINITY));
+                assert_eq!(result[4], from(2.0));
+
+                // literal arguments follow the same ordering
+                let scalars = vec![
+                    ColumnarValue::Scalar(ScalarValue::$scalar(Some(from(1.0)))),
+                    ColumnarValue::Scalar(ScalarValue::$scalar(Some(nan))),
+                ];
+                let ColumnarValue::Scalar(ScalarValue::$scalar(Some(result))) =
+                    GreatestUDF::new().invoke(&scalars).unwrap()
+                else {
+                    panic!("expected a scalar result");
+                };
+                assert!(result.is_nan());
+
+                let ColumnarValue::Scalar(ScalarValue::$scalar(Some(result))) = GreatestUDF::new()
+                    .with_nan_ordering(NanOrdering::IgnoreNan)
+                    .invoke(&scalars)
+                    .unwrap()
+                else {
+                    panic!("expected a scalar result");
+                };
+                assert_eq!(result, from(1.0));
+
+                // literal mixed with a column
+                let mixed = vec![
+                    input[0].clone(),
+                    ColumnarValue::Scalar(ScalarValue::$scalar(Some(nan))),
+                ];
+                let ColumnarValue::Array(result) = GreatestUDF::new()
+                    .with_nan_ordering(NanOrdering::IgnoreNan)
+                    .invoke(&mixed)
+                    .unwrap()
+                else {
+                    panic!("expected an array result");
+                };
+                let result = result.as_any().downcast_ref::<$array>().unwrap();
+                assert_eq!(result.value(0), from(1.0));
+                assert!(result.value(1).is_nan());
+                assert_eq!(result.value(3), from(f64::NEG_INFINITY));
+                assert_eq!(result.null_count(), 0);
+            }
+        };
+    }
+
+    test_nan_ordering!(
+        test_nan_ordering_float16,
+        Float16Array,
+        Float16,
+        |v: f64| {
+            <datafusion::arrow::datatypes::Float16Type as datafusion::arrow::datatypes::ArrowPrimitiveType>::Native::from_f64(v)
+        }
+    );
+    test_nan_ordering!(
+        test_nan_ordering_float32,
+        Float32Array,
+        Float32,
+        |v: f64| v as f32
+    );
+    test_nan_ordering!(test_nan_ordering_float64, Float64Array, Float64, |v: f64| v);
+
+    #[test]
+    fn test_nan_payloads_are_equal() {
+        // NaNs with different payloads are equal, so the first one is kept
+        let quiet = f64::NAN;
+        let payload = f64::from_bits(f64::NAN.to_bits() | 1);
+        let input = vec![
+            ColumnarValue::Array(Arc::new(Float64Array::from(vec![quiet, -payload]))),
+            ColumnarValue::Array(Arc::new(Float64Array::from(vec![payload, quiet]))),
+        ];
+        let result = greatest(&input).unwrap();
+        let result = result.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(result.value(0).to_bits(), quiet.to_bits());
+        assert_eq!(result.value(1).to_bits(), (-payload).to_bits());
+
+        let scalars = vec![
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(quiet))),
+            ColumnarValue::Scalar(ScalarValue::Float64(Some(payload))),
+        ];
+        let ColumnarValue::Scalar(ScalarValue::Float64(Some(result))) =
+            GreatestUDF::new().invoke(&scalars).unwrap()
+        else {
+            panic!("expected a scalar result");
+        };
+        assert_eq!(result.to_bits(), quiet.to_bits());
+    }
+
+    #[test]
+    fn test_greatest_propagate_nulls() {
+        let input = create_column_values(vec![
+            vec![None, Some(4), Some(7), None],
+            vec![Some(2), Some(5), Some(8), None],
+        ]);
+
+        let udf = GreatestUDF::new_with_null_handling(NullHandling::Propagate);
+        let ColumnarValue::Array(result) = udf.invoke(&input).unwrap() else {
+            panic!("expected an array result");
+        };
+
+        let answer = create_array(vec![None, Some(5), Some(8), None]);
+        assert_eq!(&result, &answer);
+
+        let udf = LeastUDF::new_with_null_handling(NullHandling::Propagate);
+        let ColumnarValue::Array(result) = udf.invoke(&input).unwrap() else {
+            panic!("expected an array result");
+        };
+
+        let answer = create_array(vec![None, Some(4), Some(7), None]);
+        assert_eq!(&result, &answer);
+    }
+
+    #[test]
+    fn test_greatest_propagate_null_scalar() {
+        let udf = GreatestUDF::new_with_null_handling(NullHandling::Propagate);
+
+        let input = vec![
+            ColumnarValue::Array(create_array(vec![Some(1), Some(2)])),
+            ColumnarValue::Scalar(ScalarValue::Int64(None)),
+        ];
+        let ColumnarValue::Array(result) = udf.invoke(&input).unwrap() else {
+            panic!("expected an array result");
+        };
+        assert_eq!(&result, &create_array(vec![None, None]));
+
+        let input = vec![
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(1))),
+            ColumnarValue::Scalar(ScalarValue::Int64(None)),
+        ];
+        let ColumnarValue::Scalar(result) = udf.invoke(&input).unwrap() else {
+            panic!("expected a scalar result");
+        };
+        assert_eq!(result, ScalarValue::Int64(None));
+    }
+
+    #[tokio::test]
+    async fn test_greatest_null_handling_names() -> Result<()> {
+        let ctx = SessionContext::new();
+        ctx.register_udf(ScalarUDF::from(GreatestUDF::new()));
+        ctx.register_udf(ScalarUDF::from(GreatestUDF::new_with_null_handling(
+            NullHandling::Propagate,
+        )));
+
+        let result = ctx
+            .sql("SELECT greatest(1, NULL, 3) AS skip, greatest_strict(1, NULL, 3) AS propagate")
+            .await?
+            .collect()
+            .await?;
+
+        assert_eq!(
+            result[0].columns()[0].as_ref(),
+            &Int64Array::from(vec![Some(3)]) as &dyn Array
+        );
+        assert_eq!(
+            result[0].columns()[1].as_ref(),
+            &Int64Array::from(vec![None]) as &dyn Array
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_greatest_return_field() {
+        use datafusion::arrow::datatypes::{DataType, Field};
+
+        let required = Field::new("a", DataType::Int32, false);
+        let optional = Field::new("b", DataType::Float64, true);
+
+        let field = GreatestUDF::new()
+            .return_field(&[required.clone(), optional.clone()])
+            .unwrap();
+        assert_eq!(field, Field::new("greatest", DataType::Float64, false));
+
+        let field = LeastUDF::new()
+            .return_field(&[optional.clone(), optional.clone()])
+            .unwrap();
+        assert_eq!(field, Field::new("least", DataType::Float64, true));
+
+        let field = GreatestUDF::new_with_null_handling(NullHandling::Propagate)
+            .return_field(&[required.clone(), optional.clone()])
+            .unwrap();
+        assert_eq!(
+            field,
+            Field::new("greatest_strict", DataType::Float64, true)
+        );
+
+        let field = LeastUDF::new_with_null_handling(NullHandling::Propagate)
+            .return_field(&[required.clone(), required.clone()])
+            .unwrap();
+        assert_eq!(field, Field::new("least_strict", DataType::Int32, false));
+
+        let result = GreatestUDF::new().return_field(&[required]);
+        assert!(result.is_err());
+    }
+
+    fn simplify_expr(expr: Expr) -> Result<Expr> {
+        use datafusion::arrow::datatypes::{Field, Schema};
+        use datafusion::common::{DFSchema, ToDFSchema};
+        use datafusion::execution::context::ExecutionProps;
+        use datafusion::optimizer::simplify_expressions::{ExprSimplifier, SimplifyContext};
+
+        let schema: Arc<DFSchema> = Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Int64, true),
+            Field::new("c", DataType::Int64, true),
+        ])
+        .to_dfschema_ref()?;
+        let props = ExecutionProps::new();
+        let simplifier = ExprSimplifier::new(SimplifyContext::new(&props).with_schema(schema));
+        simplifier.simplify(expr)
+    }
+
+    #[test]
+    fn test_greatest_simplify() -> Result<()> {
+        let greatest_udf = ScalarUDF::from(GreatestUDF::new());
+        let strict_udf =
+            ScalarUDF::from(GreatestUDF::new_with_null_handling(NullHandling::Propagate));
+        let least_udf = ScalarUDF::from(LeastUDF::new());
+
+        // literal-only calls are folded
+        let expr = greatest_udf.call(vec![lit(1i64), lit(3i64), lit(2i64)]);
+        assert_eq!(simplify_expr(expr)?, lit(3i64));
+        // with the common type of the literals
+        let expr = greatest_udf.call(vec![lit(1i64), lit(2.5f64)]);
+        assert_eq!(simplify_expr(expr)?, lit(2.5f64));
+        let expr = least_udf.call(vec![lit(2.5f64), lit(1i64)]);
+        assert_eq!(simplify_expr(expr)?, lit(1.0f64));
+
+        // duplicate arguments are removed
+        let expr = greatest_udf.call(vec![col("a"), col("a"), col("b")]);
+        assert_eq!(
+            simplify_expr(expr)?,
+            greatest_udf.call(vec![col("a"), col("b")])
+        );
+
+        // null literals are dropped when nulls are skipped
+        let expr = greatest_udf.call(vec![col("a"), lit(ScalarValue::Int64(None)), col("b")]);
+        assert_eq!(
+            simplify_expr(expr)?,
+            greatest_udf.call(vec![col("a"), col("b")])
+        );
+
+        // and make the whole call null when they are propagated
+        let expr = strict_udf.call(vec![col("a"), lit(ScalarValue::Int64(None))]);
+        assert_eq!(simplify_expr(expr)?, lit(ScalarValue::Int64(None)));
+
+        // nested calls are flattened, but only for the same function
+        let expr = greatest_udf.call(vec![greatest_udf.call(vec![col("a"), col("b")]), col("c")]);
+        assert_eq!(
+            simplify_expr(expr)?,
+            greatest_udf.call(vec![col("a"), col("b"), col("c")])
+        );
+
+        let expr = greatest_udf.call(vec![least_udf.call(vec![col("a"), col("b")]), col("c")]);
+        assert_eq!(simplify_expr(expr.clone())?, expr);
+
+        let expr = greatest_udf.call(vec![strict_udf.call(vec![col("a"), col("b")]), col("c")]);
+        assert_eq!(simplify_expr(expr.clone())?, expr);
+
+        // a single remaining argument becomes a cast
+        let expr = least_udf.call(vec![col("a"), col("a"), lit(ScalarValue::Int64(None))]);
+        assert_eq!(
+            simplify_expr(expr)?,
+            cast(col("a"), datafusion::arrow::datatypes::DataType::Int64)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_greatest_bounds() -> Result<()> {
+        use datafusion::logical_expr::interval_arithmetic::Interval;
+
+        let a = Interval::make(Some(0i64), Some(50i64))?;
+        let b = Interval::make(Some(10i64), Some(80i64))?;
+        let c = Interval::make(None, Some(30i64))?;
+
+        let bounds = GreatestUDF::new_with_null_handling(NullHandling::Propagate)
+            .evaluate_bounds(&[&a, &b, &c])?;
+        assert_eq!(bounds, Interval::make(Some(10i64), Some(80i64))?);
+
+        let bounds = LeastUDF::new_with_null_handling(NullHandling::Propagate)
+            .evaluate_bounds(&[&a, &b, &c])?;
+        assert_eq!(bounds, Interval::make(None, Some(30i64))?);
+
+        // when nulls are skipped any input can be the result: greatest(NULL, 5) = 5
+        let high = Interval::make(Some(100i64), Some(200i64))?;
+        let low = Interval::make(Some(0i64), Some(10i64))?;
+        let bounds = GreatestUDF::new().evaluate_bounds(&[&high, &low])?;
+        assert_eq!(bounds, Interval::make(Some(0i64), Some(200i64))?);
+        let ColumnarValue::Array(result) = GreatestUDF::new().invoke(&[
+            ColumnarValue::Array(create_array(vec![None])),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(5))),
+        ])?
+        else {
+            panic!("expected an array result");
+        };
+        let value = ScalarValue::try_from_array(&result, 0)?;
+        assert!(bounds.contains_value(&value)?);
+        assert!(!Interval::make(Some(100i64), Some(200i64))?.contains_value(&value)?);
+
+        let bounds = LeastUDF::new().evaluate_bounds(&[&a, &b, &c])?;
+        assert_eq!(bounds, Interval::make(None, Some(80i64))?);
+
+        // greatest(a, b) in [60, 70]: both are at most 70 and only b can reach 60
+        let output = Interval::make(Some(60i64), Some(70i64))?;
+        let result = GreatestUDF::new().propagate_constraints(&output, &[&a, &b])?;
+        assert_eq!(
+            result,
+            Some(vec![
+                Interval::make(Some(0i64), Some(50i64))?,
+                Interval::make(Some(60i64), Some(70i64))?,
+            ])
+        );
+
+        // least(a, b) in [20, 30]: both are at least 20, both can reach 30
+        let output = Interval::make(Some(20i64), Some(30i64))?;
+        let result = LeastUDF::new().propagate_constraints(&output, &[&a, &b])?;
+        assert_eq!(
+            result,
+            Some(vec![
+                Interval::make(Some(20i64), Some(50i64))?,
+                Interval::make(Some(20i64), Some(80i64))?,
+            ])
+        );
+
+        // greatest(a, b) can't be above 80
+        let output = Interval::make(Some(100i64), None)?;
+        let result = GreatestUDF::new().propagate_constraints(&output, &[&a, &b])?;
+        assert_eq!(result, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_greatest_filter_interval_analysis() -> Result<()> {
+        use datafusion::arrow::datatypes::{DataType, Field, Schema};
+        use datafusion::common::ToDFSchema;
+        use datafusion::execution::context::ExecutionProps;
+        use datafusion::logical_expr::interval_arithmetic::Interval;
+        use datafusion::physical_expr::create_physical_expr;
+        use datafusion::physical_expr::expressions::Column;
+        use datafusion::physical_expr::intervals::cp_solver::{
+            ExprIntervalGraph, PropagationResult,
+        };
+        use datafusion::physical_expr::PhysicalExpr;
+        use datafusion::physical_optimizer::pruning::PruningPredicate;
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Int64, true),
+        ]));
+        let df_schema = schema.clone().to_dfschema()?;
+
+        // WHERE greatest(a, b) > 100
+        let greatest_udf = ScalarUDF::from(GreatestUDF::new());
+        let filter = greatest_udf.call(vec![col("a"), col("b")]).gt(lit(100i64));
+        let filter = create_physical_expr(&filter, &df_schema, &ExecutionProps::new())?;
+
+        let column_a = Arc::new(Column::new("a", 0)) as Arc<dyn PhysicalExpr>;
+        let column_b = Arc::new(Column::new("b", 1)) as Arc<dyn PhysicalExpr>;
+
+        let propagate = |a: Interval, b: Interval| -> Result<(PropagationResult, Vec<Interval>)> {
+            let mut graph = ExprIntervalGraph::try_new(filter.clone(), &schema)?;
+            let indices = graph.gather_node_indices(&[column_a.clone(), column_b.clone()]);
+            let mut bounds = vec![(indices[0].1, a), (indices[1].1, b)];
+            let result = graph.update_ranges(&mut bounds, Interval::CERTAINLY_TRUE)?;
+            Ok((result, bounds.into_iter().map(|(_, i)| i).collect()))
+        };
+
+        // DataFusion 41's `PruningPredicate` doesn't run this analysis for function calls, so it can't
+        // prune any container with this filter; Parquet pruning needs a pruner built on `ExprIntervalGraph`
+        assert!(PruningPredicate::try_new(filter.clone(), schema.clone())?.always_true());
+
+        // with ranges where the filter can't match, the solver proves it infeasible,
+        // these are the bounds such a pruner would derive
+        let (result, _) = propagate(
+            Interval::make(Some(0i64), Some(50i64))?,
+            Interval::make(Some(0i64), Some(80i64))?,
+        )?;
+        assert_eq!(result, PropagationResult::Infeasible);
+
+        // only b can make the filter true, so its range is narrowed
+        let (result, bounds) = propagate(
+            Interval::make(Some(0i64), Some(50i64))?,
+            Interval::make(Some(0i64), Some(200i64))?,
+        )?;
+        assert_eq!(result, PropagationResult::Success);
+        assert_eq!(bounds[1], Interval::make(Some(101i64), Some(200i64))?);
+
+        // WHERE greatest(a, b) < 50 matches the rows where a is null and b is not,
+        // so it isn't infeasible although all values of a are above 50
+        let filter = greatest_udf.call(vec![col("a"), col("b")]).lt(lit(50i64));
+        let filter = create_physical_expr(&filter, &df_schema, &ExecutionProps::new())?;
+        let mut graph = ExprIntervalGraph::try_new(filter, &schema)?;
+        let indices = graph.gather_node_indices(&[column_a.clone(), column_b.clone()]);
+        let mut bounds = vec![
+            (indices[0].1, Interval::make(Some(100i64), Some(200i64))?),
+            (indices[1].1, Interval::make(Some(0i64), Some(10i64))?),
+        ];
+        let result = graph.update_ranges(&mut bounds, Interval::CERTAINLY_TRUE)?;
+        assert_ne!(result, PropagationResult::Infeasible);
+
+        Ok(())
+    }
+
+    async fn physical_plan(ctx: &SessionContext, sql: &str) -> Result<String> {
+        use datafusion::physical_plan::displayable;
+
+        let plan = ctx.sql(sql).await?.create_physical_plan().await?;
+        let plan = displayable(plan.as_ref()).indent(true).to_string();
+        Ok(plan)
+    }
+
+    #[tokio::test]
+    async fn test_greatest_output_ordering() -> Result<()> {
+        let config = SessionConfig::new().with_target_partitions(1);
+        let ctx = SessionContext::new_with_config(config);
+        ctx.register_udf(ScalarUDF::from(GreatestUDF::new()));
+        ctx.register_udf(ScalarUDF::from(LeastUDF::new()));
+
+        // every column of the file is sorted ascending
+        let options = CsvReadOptions::new().file_sort_order(vec![
+            vec![col("a").sort(true, true)],
+            vec![col("b").sort(true, true)],
+        ]);
+        ctx.register_csv("data", "tests/data/data.csv", options)
+            .await?;
+
+        // nulls are the smallest values for greatest(), so the ordering of a and b is reused
+        let plan = physical_plan(
+            &ctx,
+            "SELECT greatest(a, b, 5) AS g FROM data ORDER BY g NULLS FIRST",
+        )
+        .await?;
+        assert!(!plan.contains("SortExec"), "{plan}");
+
+        // the input is sorted with nulls first, so nulls last needs a sort
+        let plan = physical_plan(
+            &ctx,
+            "SELECT greatest(a, b) AS g FROM data ORDER BY g NULLS LAST",
+        )
+        .await?;
+        assert!(plan.contains("SortExec"), "{plan}");
+
+        // nulls are the largest values for least(), which doesn't match the input
+        let plan = physical_plan(
+            &ctx,
+            "SELECT least(a, b) AS l FROM data ORDER BY l NULLS FIRST",
+        )
+        .await?;
+        assert!(plan.contains("SortExec"), "{plan}");
+
+        // greatest() of a sorted and an unsorted column isn't sorted
+        let plan = physical_plan(
+            &ctx,
+            "SELECT greatest(a, c) AS g FROM data ORDER BY g NULLS FIRST",
+        )
+        .await?;
+        assert!(plan.contains("SortExec"), "{plan}");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_greatest_output_ordering_ignore_nan() -> Result<()> {
+        use datafusion::arrow::compute::SortOptions;
+        use datafusion::logical_expr::interval_arithmetic::Interval;
+        use datafusion::logical_expr::sort_properties::{ExprProperties, SortProperties};
+
+        let ascending = |datatype: &DataType| -> Result<ExprProperties> {
+            Ok(ExprProperties {
+                sort_properties: SortProperties::Ordered(SortOptions {
+                    descending: false,
+                    nulls_first: true,
+                }),
+                range: Interval::make_unbounded(datatype)?,
+            })
+        };
+        let floats = [
+            ascending(&DataType::Float64)?,
+            ascending(&DataType::Float64)?,
+        ];
+        let integers = [ascending(&DataType::Int64)?, ascending(&DataType::Int64)?];
+
+        // sorted inputs, NaN is the largest value
+        let input = vec![
+            ColumnarValue::Array(Arc::new(Float64Array::from(vec![1.0, f64::NAN]))),
+            ColumnarValue::Array(Arc::new(Float64Array::from(vec![f64::NAN, 0.5]))),
+        ];
+        let udf = GreatestUDF::new().with_nan_ordering(NanOrdering::IgnoreNan);
+        let ColumnarValue::Array(result) = udf.invoke(&input)? else {
+            panic!("expected an array result");
+        };
+        assert_eq!(
+            result.as_ref(),
+            &Float64Array::from(vec![1.0, 0.5]) as &dyn Array
+        );
+        assert_eq!(udf.output_ordering(&floats)?, SortProperties::Unordered);
+        assert_eq!(udf.output_ordering(&integers)?, integers[0].sort_properties);
+        assert_eq!(
+            LeastUDF::new()
+                .with_nan_ordering(NanOrdering::IgnoreNan)
+                .output_ordering(&floats)?,
+            SortProperties::Unordered
+        );
+
+        // with the Spark ordering the result of sorted inputs is sorted
+        assert_eq!(
+            GreatestUDF::new().output_ordering(&floats)?,
+            floats[0].sort_properties
+        );
+
+        Ok(())
+    }
+
+    fn create_struct_array(data: Vec<Option<(Option<i64>, &str)>>) -> ArrayRef {
+        use datafusion::arrow::array::StructArray;
+        use datafusion::arrow::buffer::NullBuffer;
+        use datafusion::arrow::datatypes::{Field, Fields};
+
+        let fields = Fields::from(vec![
+            Field::new("x", DataType::Int64, true),
+            Field::new("y", DataType::Utf8, true),
+        ]);
+        let nulls = NullBuffer::from(data.iter().map(|row| row.is_some()).collect::<Vec<_>>());
+        let rows = data
+            .into_iter()
+            .map(|row| row.unwrap_or((None, "")))
+            .collect::<Vec<_>>();
+        let x = create_array(rows.iter().map(|row| row.0).collect());
+        let y = Arc::new(StringArray::from(
+            rows.iter().map(|row| row.1).collect::<Vec<_>>(),
+        )) as ArrayRef;
+
+        Arc::new(StructArray::new(fields, vec![x, y], Some(nulls)))
+    }
+
+    #[test]
+    fn test_greatest_structs() {
+        let input = vec![
+            ColumnarValue::Array(create_struct_array(vec![
+                Some((Some(1), "b")),
+                Some((None, "a")),
+                None,
+                Some((Some(2), "a")),
+                None,
+            ])),
+            ColumnarValue::Array(create_struct_array(vec![
+                Some((Some(1), "c")),
+                Some((Some(0), "a")),
+                Some((Some(5), "z")),
+                None,
+                None,
+            ])),
+        ];
+
+        // fields are compared in order, a null field is smaller than any value
+        let result = greatest(&input).unwrap();
+        let answer = create_struct_array(vec![
+            Some((Some(1), "c")),
+            Some((Some(0), "a")),
+            Some((Some(5), "z")),
+            Some((Some(2), "a")),
+            None,
+        ]);
+        assert_eq!(&result, &answer);
+
+        let result = least(&input).unwrap();
+        let answer = create_struct_array(vec![
+            Some((Some(1), "b")),
+            Some((None, "a")),
+            Some((Some(5), "z")),
+            Some((Some(2), "a")),
+            None,
+        ]);
+        assert_eq!(&result, &answer);
+    }
+
+    #[test]
+    fn test_make_builder_struct() {
+        let array = create_struct_array(vec![Some((Some(1), "a")), None, Some((None, "b"))]);
+
+        let mut builder = make_builder(array.data_type(), array.len()).unwrap();
+        for i in 0..array.len() {
+            let value = ScalarValue::try_from_array(&array, i).unwrap();
+            builder.append_scalar_value(value).unwrap();
+        }
+        builder.append_scalar_value(ScalarValue::Null).unwrap();
+
+        // a struct whose second field is rejected doesn't grow the first child
+        let invalid = datafusion::arrow::array::StructArray::from(vec![
+            (
+                Arc::new(datafusion::arrow::datatypes::Field::new(
+                    "x",
+                    DataType::Int64,
+                    true,
+                )),
+                create_array(vec![Some(2)]),
+            ),
+            (
+                Arc::new(datafusion::arrow::datatypes::Field::new(
+                    "y",
+                    DataType::Int64,
+                    true,
+                )),
+                create_array(vec![Some(3)]),
+            ),
+        ]);
+        let error = builder
+            .append_scalar_value(ScalarValue::Struct(Arc::new(invalid)))
+            .unwrap_err();
+        assert!(matches!(error, DataFusionError::External(_)));
+
+        let answer = create_struct_array(vec![Some((Some(1), "a")), None, Some((None, "b")), None]);
+        assert_eq!(&builder.finish(), &answer);
+    }
+
+    fn create_list_array(data: Vec<Option<Vec<Option<i64>>>>) -> ArrayRef {
+        use datafusion::arrow::array::ListArray;
+        use datafusion::arrow::datatypes::Int64Type;
+
+        Arc::new(ListArray::from_iter_primitive::<Int64Type, _, _>(data))
+    }
+
+    #[test]
+    fn test_greatest_lists_ordering() {
+        let input = vec![
+            ColumnarValue::Array(create_list_array(vec![
+                Some(vec![Some(1), Some(2)]),
+                Some(vec![None, Some(5)]),
+                None,
+                Some(vec![]),
+                None,
+            ])),
+            ColumnarValue::Array(create_list_array(vec![
+                Some(vec![Some(1), Some(2), Some(0)]),
+                Some(vec![Some(0)]),
+                Some(vec![Some(3), None]),
+                Some(vec![None]),
+                None,
+            ])),
+        ];
+
+        // a prefix is smaller than the longer list, a null element is smaller than any value
+        let result = greatest(&input).unwrap();
+        let answer = create_list_array(vec![
+            Some(vec![Some(1), Some(2), Some(0)]),
+            Some(vec![Some(0)]),
+            Some(vec![Some(3), None]),
+            Some(vec![None]),
+            None,
+        ]);
+        assert_eq!(&result, &answer);
+
+        let result = least(&input).unwrap();
+        let answer = create_list_array(vec![
+            Some(vec![Some(1), Some(2)]),
+            Some(vec![None, Some(5)]),
+            Some(vec![Some(3), None]),
+            Some(vec![]),
+            None,
+        ]);
+        assert_eq!(&result, &answer);
+    }
+
+    #[test]
+    fn test_greatest_large_and_fixed_size_lists() {
+        use datafusion::arrow::array::{FixedSizeListArray, LargeListArray};
+        use datafusion::arrow::datatypes::Int64Type;
+
+        let large = |data: Vec<Option<Vec<Option<i64>>>>| {
+            ColumnarValue::Array(Arc::new(LargeListArray::from_iter_primitive::<
+                Int64Type,
+                _,
+                _,
+            >(data)))
+        };
+        let input = vec![
+            large(vec![Some(vec![Some(2)]), None, Some(vec![None, Some(1)])]),
+            large(vec![Some(vec![Some(1), Some(9)]), None, Some(vec![None])]),
+        ];
+        let result = greatest(&input).unwrap();
+        let answer = LargeListArray::from_iter_primitive::<Int64Type, _, _>(vec![
+            Some(vec![Some(2)]),
+            None,
+            Some(vec![None, Some(1)]),
+        ]);
+        assert_eq!(result.as_ref(), &answer as &dyn Array);
+
+        let fixed = |data: Vec<Option<Vec<Option<i64>>>>| {
+            Arc::new(FixedSizeListArray::from_iter_primitive::<Int64Type, _, _>(
+                data, 2,
+            )) as ArrayRef
+        };
+        let input = vec![
+            ColumnarValue::Array(fixed(vec![
+                Some(vec![Some(1), Some(2)]),
+                None,
+                Some(vec![None, Some(7)]),
+            ])),
+            ColumnarValue::Array(fixed(vec![
+                Some(vec![Some(1), Some(3)]),
+                None,
+                Some(vec![Some(0), None]),
+            ])),
+        ];
+        let result = least(&input).unwrap();
+        let answer = fixed(vec![
+            Some(vec![Some(1), Some(2)]),
+            None,
+            Some(vec![None, Some(7)]),
+        ]);
+        assert_eq!(&result, &answer);
+    }
+
+    #[test]
+    fn test_make_builder_null_lists() {
+        let array = create_list_array(vec![Some(vec![Some(1), None]), None, Some(vec![])]);
+
+        let mut builder = make_builder(array.data_type(), array.len()).unwrap();
+        for i in 0..array.len() {
+            let value = ScalarValue::try_from_array(&array, i).unwrap();
+            builder.append_scalar_value(value).unwrap();
+        }
+        builder.append_scalar_value(ScalarValue::Null).unwrap();
+
+        let answer = create_list_array(vec![Some(vec![Some(1), None]), None, Some(vec![]), None]);
+        assert_eq!(&builder.finish(), &answer);
+    }
+
+    fn decode(array: &dyn Array) -> ArrayRef {
+        datafusion::arrow::compute::cast(array, &DataType::Utf8).unwrap()
+    }
+
+    #[test]
+    fn test_greatest_shared_dictionary() {
+        use datafusion::arrow::array::{DictionaryArray, Int32Array};
+        use datafusion::arrow::datatypes::Int32Type;
+
+        let values = Arc::new(StringArray::from(vec![
+            Some("b"),
+            Some("a"),
+            None,
+            Some("c"),
+        ])) as ArrayRef;
+        let dictionary = |keys: Vec<Option<i32>>| {
+            Arc::new(DictionaryArray::<Int32Type>::new(
+                Int32Array::from(keys),
+                values.clone(),
+            )) as ArrayRef
+        };
+        let input = vec![
+            ColumnarValue::Array(dictionary(vec![Some(0), Some(1), None, Some(2), Some(3)])),
+            ColumnarValue::Array(dictionary(vec![Some(1), Some(3), None, None, Some(0)])),
+        ];
+
+        // keys are compared through the ranks of the values, the result keeps the dictionary
+        let result = greatest(&input).unwrap();
+        let result = result
+            .as_any()
+            .downcast_ref::<DictionaryArray<Int32Type>>()
+            .unwrap();
+        assert!(result.values().to_data().ptr_eq(&values.to_data()));
+        let answer = DictionaryArray::<Int32Type>::from_iter(vec![
+            Some("b"),
+            Some("c"),
+            None,
+            None,
+            Some("c"),
+        ]);
+        assert_eq!(&decode(result), &decode(&answer));
+
+        let result = least(&input).unwrap();
+        let answer = DictionaryArray::<Int32Type>::from_iter(vec![
+            Some("a"),
+            Some("a"),
+            None,
+            None,
+            Some("b"),
+        ]);
+        assert_eq!(&decode(&result), &decode(&answer));
+    }
+
+    #[test]
+    fn test_greatest_different_dictionaries() {
+        use datafusion::arrow::array::DictionaryArray;
+        use datafusion::arrow::datatypes::Int32Type;
+
+        let input = vec![
+            ColumnarValue::Array(Arc::new(DictionaryArray::<Int32Type>::from_iter(vec![
+                Some("x"),
+                None,
+                Some("a"),
+            ]))),
+            ColumnarValue::Array(Arc::new(DictionaryArray::<Int32Type>::from_iter(vec![
+                Some("y"),
+                None,
+                Some("b"),
+            ]))),
+            ColumnarValue::Scalar(ScalarValue::Dictionary(
+                Box::new(DataType::Int32),
+                Box::new(ScalarValue::from("m")),
+            )),
+        ];
+
+        let result = greatest(&input).unwrap();
+        assert_eq!(
+            result.data_type(),
+            &DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8))
+        );
+        let answer = StringArray::from(vec![Some("y"), Some("m"), Some("m")]);
+        assert_eq!(&decode(&result), &(Arc::new(answer) as ArrayRef));
+
+        // views have no arrow dictionary builder
+        let views = |keys: Vec<Option<i32>>, values: Vec<&str>| {
+            let values = datafusion::arrow::array::StringViewArray::from(values);
+            let dictionary = DictionaryArray::<Int32Type>::try_new(
+                datafusion::arrow::array::Int32Array::from(keys),
+                Arc::new(values),
+            )
+            .unwrap();
+            ColumnarValue::Array(Arc::new(dictionary))
+        };
+        let input = vec![
+            views(vec![Some(0), None, Some(1)], vec!["x", "a long value of a"]),
+            views(vec![Some(1), None, Some(0)], vec!["b", "y"]),
+        ];
+        let result = greatest(&input).unwrap();
+        assert_eq!(
+            result.data_type(),
+            &DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8View))
+        );
+        let answer = StringArray::from(vec![Some("y"), None, Some("b")]);
+        assert_eq!(&decode(&result), &(Arc::new(answer) as ArrayRef));
+    }
+
+    #[test]
+    fn test_make_builder_dictionary() {
+        use datafusion::arrow::array::DictionaryArray;
+        use datafusion::arrow::datatypes::{Int8Type, UInt16Type};
+
+        let array = Arc::new(DictionaryArray::<Int8Type>::from_iter(vec![
+            Some("a"),
+            None,
+            Some("b"),
+            Some("a"),
+        ])) as ArrayRef;
+        let mut builder = make_builder(array.data_type(), array.len()).unwrap();
+        for i in 0..array.len() {
+            let value = ScalarValue::try_from_array(&array, i).unwrap();
+            builder.append_scalar_value(value).unwrap();
+        }
+        assert_eq!(&builder.finish(), &array);
+
+        let datatype = DataType::Dictionary(Box::new(DataType::UInt16), Box::new(DataType::Int64));
+        let mut builder = make_builder(&datatype, 3).unwrap();
+        builder
+            .append_scalar_value(ScalarValue::Int64(Some(7)))
+            .unwrap();
+        builder.append_scalar_value(ScalarValue::Null).unwrap();
+        builder
+            .append_scalar_value(ScalarValue::Int64(Some(7)))
+            .unwrap();
+        let result = builder.finish();
+        let result = result
+            .as_any()
+            .downcast_ref::<DictionaryArray<UInt16Type>>()
+            .unwrap();
+        assert_eq!(result.values().len(), 1);
+        assert_eq!(result.null_count(), 1);
+
+        assert!(builder.append_scalar_value(ScalarValue::from("a")).is_err());
+
+        // decimal values keep their precision and scale, also the values of dictionary scalars
+        let value_type = DataType::Decimal128(10, 2);
+        let datatype = DataType::Dictionary(Box::new(DataType::Int8), Box::new(value_type.clone()));
+        let mut builder = make_builder(&datatype, 3).unwrap();
+        builder
+            .append_scalar_value(ScalarValue::Decimal128(Some(150), 10, 2))
+            .unwrap();
+        builder
+            .append_scalar_value(ScalarValue::Dictionary(
+                Box::new(DataType::Int8),
+                Box::new(ScalarValue::Decimal128(Some(150), 10, 2)),
+            ))
+            .unwrap();
+        builder.append_nulls(1).unwrap();
+        let error = builder
+            .append_scalar_value(ScalarValue::Decimal128(Some(150), 10, 3))
+            .unwrap_err();
+        assert_eq!(type_mismatch(error).expected, datatype);
+        assert_eq!(builder.finish_cloned().data_type(), &datatype);
+        let result = builder.finish();
+        let result = result
+            .as_any()
+            .downcast_ref::<DictionaryArray<Int8Type>>()
+            .unwrap();
+        assert_eq!(result.values().len(), 1);
+        assert_eq!(result.values().data_type(), &value_type);
+        assert_eq!(
+            result.keys(),
+            &datafusion::arrow::array::Int8Array::from(vec![Some(0), Some(0), None])
+        );
+    }
+
+    #[test]
+    fn test_greatest_string_views() {
+        use datafusion::arrow::array::{AsArray, BinaryViewArray, StringViewArray};
+
+        // strings up to 12 bytes are inlined in the views, longer ones live in data buffers
+        let left = Arc::new(StringViewArray::from(vec![
+            Some("short"),
+            Some("a string that is stored out of line"),
+            None,
+            Some("zz"),
+        ])) as ArrayRef;
+        let right = Arc::new(StringViewArray::from(vec![
+            Some("another long string in a data buffer"),
+            Some("b"),
+            Some("tiny"),
+            None,
+        ])) as ArrayRef;
+        let input = vec![
+            ColumnarValue::Array(left.clone()),
+            ColumnarValue::Array(right.clone()),
+        ];
+
+        let result = greatest(&input).unwrap();
+        let answer = Arc::new(StringViewArray::from(vec![
+            Some("short"),
+            Some("b"),
+            Some("tiny"),
+            Some("zz"),
+        ])) as ArrayRef;
+        assert_eq!(&result, &answer);
+
+        let result = least(&input).unwrap();
+        let answer = Arc::new(StringViewArray::from(vec![
+            Some("another long string in a data buffer"),
+            Some("a string that is stored out of line"),
+            Some("tiny"),
+            Some("zz"),
+        ])) as ArrayRef;
+        assert_eq!(&result, &answer);
+
+        // the long strings are not copied, the result points into the input buffers
+        let input_buffers = [left, right]
+            .iter()
+            .flat_map(|array| array.as_string_view().data_buffers().to_vec())
+            .collect::<Vec<_>>();
+        for buffer in result.as_string_view().data_buffers() {
+            assert!(input_buffers
+                .iter()
+                .any(|input| input.as_ptr() == buffer.as_ptr()));
+        }
+
+        let input = vec![
+            ColumnarValue::Array(Arc::new(BinaryViewArray::from(vec![
+                Some(b"binary value longer than twelve".as_slice()),
+                None,
+            ]))),
+            ColumnarValue::Scalar(ScalarValue::BinaryView(Some(b"c".to_vec()))),
+        ];
+        let result = greatest(&input).unwrap();
+        let answer = Arc::new(BinaryViewArray::from(vec![
+            Some(b"c".as_slice()),
+            Some(b"c".as_slice()),
+        ])) as ArrayRef;
+        assert_eq!(&result, &answer);
+    }
+
+    #[test]
+    fn test_make_builder_views() {
+        use datafusion::arrow::array::StringViewArray;
+
+        let array = Arc::new(StringViewArray::from(vec![
+            Some("inline"),
+            None,
+            Some("a value that does not fit into a view"),
+        ])) as ArrayRef;
+        let mut builder = make_builder(array.data_type(), array.len()).unwrap();
+        for i in 0..array.len() {
+            let value = ScalarValue::try_from_array(&array, i).unwrap();
+            builder.append_scalar_value(value).unwrap();
+        }
+        assert_eq!(&builder.finish(), &array);
+
+        let mut builder = make_builder(&DataType::BinaryView, 2).unwrap();
+        builder
+            .append_scalar_value(ScalarValue::BinaryView(Some(vec![1, 2])))
+            .unwrap();
+        builder.append_scalar_value(ScalarValue::Null).unwrap();
+        assert_eq!(builder.finish().null_count(), 1);
+    }
+
+    type MapEntries<'a> = Vec<(&'a str, Option<i64>)>;
+
+    fn create_map_array(data: Vec<Option<MapEntries>>) -> ArrayRef {
+        use datafusion::arrow::array::{MapBuilder, StringBuilder};
+
+        let mut builder = MapBuilder::new(None, StringBuilder::new(), Int64Builder::new());
+        for row in data {
+            for (key, value) in row.iter().flatten() {
+                builder.keys().append_value(key);
+                builder.values().append_option(*value);
+            }
+            builder.append(row.is_some()).unwrap();
+        }
+        Arc::new(builder.finish())
+    }
+
+    #[test]
+    fn test_make_builder_map() {
+        let array = create_map_array(vec![
+            Some(vec![("b", Some(2)), ("a", None)]),
+            None,
+            Some(vec![]),
+            Some(vec![("c", Some(3))]),
+        ]);
+
+        let mut builder = make_builder(array.data_type(), array.len()).unwrap();
+        for i in 0..array.len() {
+            let value = ScalarValue::try_from_array(&array, i).unwrap();
+            assert!(matches!(value, ScalarValue::Map(_)));
+            builder.append_scalar_value(value).unwrap();
+        }
+        builder.append_scalar_value(ScalarValue::Null).unwrap();
+
+        // a map with values of another type is rejected before its first key is appended
+        let mut int32_map = datafusion::arrow::array::MapBuilder::new(
+            None,
+            datafusion::arrow::array::StringBuilder::new(),
+            datafusion::arrow::array::Int32Builder::new(),
+        );
+        int32_map.keys().append_value("d");
+        int32_map.values().append_value(4);
+        int32_map.append(true).unwrap();
+        let invalid = ScalarValue::Map(Arc::new(int32_map.finish()));
+        let error = builder.append_scalar_value(invalid).unwrap_err();
+        assert_eq!(type_mismatch(error).expected, array.data_type().clone());
+        let valid = create_map_array(vec![Some(vec![("e", Some(5))])]);
+        builder
+            .append_scalar_value(ScalarValue::try_from_array(&valid, 0).unwrap())
+            .unwrap();
+
+        let answer = create_map_array(vec![
+            Some(vec![("b", Some(2)), ("a", None)]),
+            None,
+            Some(vec![]),
+            Some(vec![("c", Some(3))]),
+            None,
+            Some(vec![("e", Some(5))]),
+        ]);
+        assert_eq!(&builder.finish(), &answer);
+    }
+
+    #[test]
+    fn test_make_builder_sorted_map() {
+        let array = create_map_array(vec![Some(vec![("a", Some(1)), ("b", None)]), None]);
+        let DataType::Map(field, false) = array.data_type() else {
+            unreachable!()
+        };
+        let datatype = DataType::Map(field.clone(), true);
+
+        let mut builder = make_builder(&datatype, array.len()).unwrap();
+        for i in 0..array.len() {
+            let value = ScalarValue::try_from_array(&array, i).unwrap();
+            builder.append_scalar_value(value).unwrap();
+        }
+        let result = builder.finish();
+        assert_eq!(result.data_type(), &datatype);
+        assert_eq!(result.len(), 2);
+        assert!(result.is_null(1));
+
+        // keys of a sorted map must be in ascending order
+        let unsorted = create_map_array(vec![Some(vec![("b", Some(1)), ("a", Some(2))])]);
+        let value = ScalarValue::try_from_array(&unsorted, 0).unwrap();
+        assert!(builder.append_scalar_value(value).is_err());
+        // and no key of the rejected map is kept
+        let value = ScalarValue::try_from_array(&array, 0).unwrap();
+        builder.append_scalar_value(value).unwrap();
+        let result = builder.finish();
+        let map = datafusion::arrow::array::AsArray::as_map(result.as_ref());
+        assert_eq!(map.keys().len(), 2);
+    }
+
+    #[test]
+    fn test_make_builder_run_end_encoded() {
+        use datafusion::arrow::array::{AsArray, Int32Array, RunArray};
+        use datafusion::arrow::datatypes::{Field, Int32Type};
+
+        let datatype = DataType::RunEndEncoded(
+            Arc::new(Field::new("run_ends", DataType::Int32, false)),
+            Arc::new(Field::new("values", DataType::Utf8, true)),
+        );
+        let mut builder = make_builder(&datatype, 6).unwrap();
+        for value in [Some("a"), Some("a"), None, None, Some("b"), Some("a")] {
+            builder
+                .append_scalar_value(ScalarValue::Utf8(value.map(String::from)))
+                .unwrap();
+        }
+        builder.append_scalar_value(ScalarValue::Null).unwrap();
+
+        // repeated values extend the current run
+        let result = builder.finish();
+        assert_eq!(result.data_type(), &datatype);
+        assert_eq!(result.len(), 7);
+        let result = result
+            .as_any()
+            .downcast_ref::<RunArray<Int32Type>>()
+            .unwrap();
+        assert_eq!(result.run_ends().values(), &[2, 4, 5, 6, 7]);
+        assert_eq!(
+            result.values().as_string::<i32>(),
+            &StringArray::from(vec![Some("a"), None, Some("b"), Some("a"), None])
+        );
+
+        let run_array = |run_ends: Vec<i32>, values: Vec<Option<i64>>| {
+            Arc::new(
+                RunArray::<Int32Type>::try_new(
+                    &Int32Array::from(run_ends),
+                    &Int64Array::from(values),
+                )
+                .unwrap(),
+            ) as ArrayRef
+        };
+        let input = vec![
+            ColumnarValue::Array(run_array(vec![3, 6], vec![Some(1), Some(5)])),
+            ColumnarValue::Array(run_array(vec![2, 4, 6], vec![Some(3), None, Some(2)])),
+        ];
+        let result = greatest(&input).unwrap();
+        assert!(matches!(result.data_type(), DataType::RunEndEncoded(_, _)));
+        let result = result
+            .as_any()
+            .downcast_ref::<RunArray<Int32Type>>()
+            .unwrap();
+        assert_eq!(result.run_ends().values(), &[2, 3, 6]);
+        assert_eq!(
+            result
+                .values()
+                .as_primitive::<datafusion::arrow::datatypes::Int64Type>(),
+            &Int64Array::from(vec![3, 1, 5])
+        );
+    }
+
+    #[test]
+    fn test_make_builder_union() {
+        use datafusion::arrow::array::{Int32Array, UnionArray};
+        use datafusion::arrow::buffer::ScalarBuffer;
+        use datafusion::arrow::datatypes::{Field, UnionFields};
+
+        let fields = UnionFields::new(
+            vec![0, 3],
+            vec![
+                Field::new("int", DataType::Int32, true),
+                Field::new("str", DataType::Utf8, true),
+            ],
+        );
+        let sparse = Arc::new(
+            UnionArray::try_new(
+                fields.clone(),
+                ScalarBuffer::from(vec![0, 3, 0, 3]),
+                None,
+                vec![
+                    Arc::new(Int32Array::from(vec![Some(1), None, None, None])),
+                    Arc::new(StringArray::from(vec![None, Some("x"), None, Some("y")])),
+                ],
+            )
+            .unwrap(),
+        ) as ArrayRef;
+        let dense = Arc::new(
+            UnionArray::try_new(
+                fields,
+                ScalarBuffer::from(vec![0, 3, 0, 3]),
+                Some(ScalarBuffer::from(vec![0, 0, 1, 1])),
+                vec![
+                    Arc::new(Int32Array::from(vec![Some(1), None])),
+                    Arc::new(StringArray::from(vec![Some("x"), Some("y")])),
+                ],
+            )
+            .unwrap(),
+        ) as ArrayRef;
+
+        for array in [sparse, dense] {
+            let DataType::Union(fields, mode) = array.data_type().clone() else {
+                unreachable!()
+            };
+            let mut builder = make_builder(array.data_type(), array.len()).unwrap();
+            for i in 0..array.len() {
+                // a value rejected by its child leaves the builder as it was
+                for (type_id, invalid) in [(0, ScalarValue::from("bad")), (3, ScalarValue::from(1))]
+                {
+                    let invalid = ScalarValue::Union(
+                        Some((type_id, Box::new(invalid))),
+                        fields.clone(),
+                        mode,
+                    );
+                    assert!(builder.append_scalar_value(invalid).is_err());
+                }
+                let value = builder::scalar_from_array(&array, i).unwrap();
+                builder.append_scalar_value(value).unwrap();
+            }
+            let result = builder.finish();
+            assert_eq!(result.data_type(), array.data_type());
+            assert_eq!(result.to_data(), array.to_data());
+        }
+    }
+
+    #[test]
+    fn test_make_builder_list_view_unsupported() {
+        use datafusion::arrow::datatypes::Field;
+
+        let datatype = DataType::ListView(Arc::new(Field::new("item", DataType::Int64, true)));
+        assert!(matches!(
+            make_builder(&datatype, 1),
+            Err(DataFusionError::NotImplemented(_))
+        ));
+    }
+
+    #[test]
+    fn test_builder_bulk_append() {
+        let array = create_array(vec![Some(1), None, Some(3), Some(4), None]);
+        let mut builder = make_builder(&DataType::Int64, 8).unwrap();
+        builder
+            .append_array_slice(&array.slice(1, 4), 1, 2)
+            .unwrap();
+        builder
+            .append_scalar_n(ScalarValue::Int64(Some(7)), 2)
+            .unwrap();
+        builder.append_nulls(2).unwrap();
+        builder.append_array_slice(&array, 0, 2).unwrap();
+        assert_eq!(
+            &builder.finish(),
+            &create_array(vec![
+                Some(3),
+                Some(4),
+                Some(7),
+                Some(7),
+                None,
+                None,
+                Some(1),
+                None
+            ])
+        );
+
+        assert!(builder.append_array_slice(&array, 4, 2).is_err());
+        let strings = Arc::new(StringArray::from(vec!["a"])) as ArrayRef;
+        assert!(builder.append_array_slice(&strings, 0, 1).is_err());
+
+        let strings = Arc::new(StringArray::from(vec![
+            Some("a"),
+            None,
+            Some("long value c"),
+        ])) as ArrayRef;
+        let mut builder = make_builder(&DataType::Utf8, 4).unwrap();
+        builder.append_array_slice(&strings, 1, 2).unwrap();
+        builder
+            .append_scalar_n(ScalarValue::Utf8(Some("d".to_string())), 2)
+            .unwrap();
+        assert_eq!(
+            &builder.finish(),
+            &(Arc::new(StringArray::from(vec![
+                None,
+                Some("long value c"),
+                Some("d"),
+                Some("d")
+            ])) as ArrayRef)
+        );
+
+        // long view values are appended as views into the shared data buffer of the slice
+        let views = Arc::new(datafusion::arrow::array::StringViewArray::from(vec![
+            Some("a long value of the first row"),
+            Some("short"),
+            None,
+            Some("a long value of the last row"),
+        ])) as ArrayRef;
+        let mut builder = make_builder(&DataType::Utf8View, 6).unwrap();
+        builder
+            .append_scalar_value(ScalarValue::Utf8View(Some(
+                "a long value appended before".to_string(),
+            )))
+            .unwrap();
+        builder.append_array_slice(&views, 0, 4).unwrap();
+        builder.append_array_slice(&views, 3, 1).unwrap();
+        let result = builder.finish();
+        assert_eq!(
+            result.as_ref(),
+            &datafusion::arrow::array::StringViewArray::from(vec![
+                Some("a long value appended before"),
+                Some("a long value of the first row"),
+                Some("short"),
+                None,
+                Some("a long value of the last row"),
+                Some("a long value of the last row"),
+            ]) as &dyn Array
+        );
+        // the builder's own block and the shared buffer, once for each slice
+        assert_eq!(
+            datafusion::arrow::array::AsArray::as_string_view(result.as_ref())
+                .data_buffers()
+                .len(),
+            3
+        );
+
+        let datatype = DataType::Decimal128(10, 2);
+        let mut builder = make_builder(&datatype, 3).unwrap();
+        builder
+            .append_scalar_n(ScalarValue::Decimal128(Some(150), 10, 2), 2)
+            .unwrap();
+        builder.append_nulls(1).unwrap();
+        let result = builder.finish();
+        assert_eq!(result.data_type(), &datatype);
+        assert_eq!(
+            result.as_ref(),
+            &Decimal128Array::from(vec![Some(150), Some(150), None])
+                .with_data_type(datatype.clone()) as &dyn Array
+        );
+
+        let binaries = Arc::new(
+            datafusion::arrow::array::FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+                vec![Some(b"ab"), None, Some(b"cd")].into_iter(),
+                2,
+            )
+            .unwrap(),
+        ) as ArrayRef;
+        let mut builder = make_builder(binaries.data_type(), 3).unwrap();
+        builder.append_array_slice(&binaries, 1, 2).unwrap();
+        builder.append_nulls(1).unwrap();
+        let error = builder.append_array_slice(&strings, 0, 1).unwrap_err();
+        assert_eq!(
+            type_mismatch(error),
+            TypeMismatch {
+                expected: DataType::FixedSizeBinary(2),
+                actual: DataType::Utf8
+            }
+        );
+        let answer =
+            datafusion::arrow::array::FixedSizeBinaryArray::try_from_sparse_iter_with_size(
+                vec![None, Some(b"cd"), None].into_iter(),
+                2,
+            )
+            .unwrap();
+        assert_eq!(builder.finish().as_ref(), &answer as &dyn Array);
+    }
+
+    #[test]
+    fn test_builder_bulk_append_nested() {
+        let lists = create_list_array(vec![
+            Some(vec![Some(1), Some(2)]),
+            None,
+            Some(vec![]),
+            Some(vec![None, Some(4)]),
+        ]);
+        let mut builder = make_builder(lists.data_type(), 4).unwrap();
+        builder.append_array_slice(&lists, 1, 3).unwrap();
+        builder.append_nulls(1).unwrap();
+        builder.append_array_slice(&lists, 0, 1).unwrap();
+        assert_eq!(
+            &builder.finish(),
+            &create_list_array(vec![
+                None,
+                Some(vec![]),
+                Some(vec![None, Some(4)]),
+                None,
+                Some(vec![Some(1), Some(2)]),
+            ])
+        );
+
+        let structs = create_struct_array(vec![Some((Some(1), "a")), None, Some((None, "c"))]);
+        let mut builder = make_builder(structs.data_type(), 4).unwrap();
+        builder
+            .append_array_slice(&structs.slice(1, 2), 0, 2)
+            .unwrap();
+        builder.append_array_slice(&structs, 0, 1).unwrap();
+        assert_eq!(
+            &builder.finish(),
+            &create_struct_array(vec![None, Some((None, "c")), Some((Some(1), "a"))])
+        );
+
+        // structs with another field type or fewer fields are rejected before any child is appended
+        let int64 = |name| {
+            let field = datafusion::arrow::datatypes::Field::new(name, DataType::Int64, true);
+            (Arc::new(field), create_array(vec![Some(1)]))
+        };
+        let mismatched = Arc::new(datafusion::arrow::array::StructArray::from(vec![
+            int64("x"),
+            int64("y"),
+        ])) as ArrayRef;
+        let too_few = Arc::new(datafusion::arrow::array::StructArray::from(vec![int64(
+            "x",
+        )])) as ArrayRef;
+        let mut builder = make_builder(structs.data_type(), 2).unwrap();
+        let error = builder.append_array_slice(&mismatched, 0, 1).unwrap_err();
+        assert_eq!(type_mismatch(error).actual, mismatched.data_type().clone());
+        let error = builder.append_array_slice(&too_few, 0, 1).unwrap_err();
+        assert_eq!(type_mismatch(error).actual, too_few.data_type().clone());
+        builder.append_array_slice(&structs, 2, 1).unwrap();
+        assert_eq!(
+            &builder.finish(),
+            &create_struct_array(vec![Some((None, "c"))])
+        );
+
+        let fixed = Arc::new(
+            datafusion::arrow::array::FixedSizeListArray::from_iter_primitive::<
+                datafusion::arrow::datatypes::Int64Type,
+                _,
+                _,
+            >(
+                vec![
+                    Some(vec![Some(1), Some(2)]),
+                    None,
+                    Some(vec![Some(5), None]),
+                ],
+                2,
+            ),
+        ) as ArrayRef;
+        let mut builder = make_builder(fixed.data_type(), 2).unwrap();
+        builder.append_array_slice(&fixed, 1, 2).unwrap();
+        assert_eq!(&builder.finish(), &fixed.slice(1, 2));
+
+        let datatype = DataType::RunEndEncoded(
+            Arc::new(datafusion::arrow::datatypes::Field::new(
+                "run_ends",
+                DataType::Int16,
+                false,
+            )),
+            Arc::new(datafusion::arrow::datatypes::Field::new(
+                "values",
+                DataType::Int64,
+                true,
+            )),
+        );
+        let mut builder = make_builder(&datatype, 8).unwrap();
+        builder
+            .append_scalar_n(ScalarValue::Int64(Some(1)), 3)
+            .unwrap();
+        builder
+            .append_scalar_n(ScalarValue::Int64(Some(1)), 2)
+            .unwrap();
+        builder.append_nulls(3).unwrap();
+        let result = builder.finish();
+        assert_eq!(result.len(), 8);
+        let result = result
+            .as_any()
+            .downcast_ref::<datafusion::arrow::array::RunArray<datafusion::arrow::datatypes::Int16Type>>()
+            .unwrap();
+        assert_eq!(result.run_ends().values(), &[5, 8]);
+    }
+
+    fn type_mismatch(error: DataFusionError) -> TypeMismatch {
+        match error {
+            DataFusionError::External(error) => {
+                error.downcast_ref::<TypeMismatch>().unwrap().clone()
+            }
+            error => panic!("expected a type mismatch, got {error}"),
+        }
+    }
+
+    #[test]
+    fn test_builder_type_mismatch() {
+        let mut builder = make_builder(&DataType::Int64, 2).unwrap();
+        assert_eq!(builder.data_type(), DataType::Int64);
+        let error = builder
+            .append_scalar_value(ScalarValue::Utf8(Some("a".to_string())))
+            .unwrap_err();
+        assert_eq!(
+            type_mismatch(error),
+            TypeMismatch {
+                expected: DataType::Int64,
+                actual: DataType::Utf8
+            }
+        );
+
+        // slices of another type are rejected with the same error by the plain builders
+        let int32 = Arc::new(datafusion::arrow::array::Int32Array::from(vec![1])) as ArrayRef;
+        for datatype in [
+            DataType::Int64,
+            DataType::Utf8,
+            DataType::Utf8View,
+            DataType::Boolean,
+        ] {
+            let mut builder = make_builder(&datatype, 2).unwrap();
+            let error = builder.append_array_slice(&int32, 0, 1).unwrap_err();
+            assert_eq!(
+                type_mismatch(error),
+                TypeMismatch {
+                    expected: datatype,
+                    actual: DataType::Int32
+                }
+            );
+        }
+
+        let list = create_list_array(vec![Some(vec![Some(1)])]);
+        let mut builder = make_builder(list.data_type(), 2).unwrap();
+        assert_eq!(&builder.data_type(), list.data_type());
+        let error = builder
+            .append_scalar_value(ScalarValue::Int64(Some(1)))
+            .unwrap_err();
+        assert_eq!(type_mismatch(error).expected, list.data_type().clone());
+        let error = builder.append_array_slice(&int32, 0, 1).unwrap_err();
+        assert_eq!(type_mismatch(error).actual, DataType::Int32);
+
+        // null and empty lists have no elements to reject, their list type is checked
+        let int32_list = |datatype: fn(Arc<datafusion::arrow::datatypes::Field>) -> DataType| {
+            datatype(Arc::new(datafusion::arrow::datatypes::Field::new(
+                "item",
+                DataType::Int32,
+                true,
+            )))
+        };
+        let int64_list = |datatype: fn(Arc<datafusion::arrow::datatypes::Field>) -> DataType| {
+            datatype(Arc::new(datafusion::arrow::datatypes::Field::new(
+                "item",
+                DataType::Int64,
+                true,
+            )))
+        };
+        let fixed_size = |field| DataType::FixedSizeList(field, 2);
+        for datatype in [DataType::List, DataType::LargeList, fixed_size] {
+            let mut builder = make_builder(&int64_list(datatype), 2).unwrap();
+            let null = ScalarValue::try_from(&int32_list(datatype)).unwrap();
+            let error = builder.append_scalar_value(null).unwrap_err();
+            assert_eq!(type_mismatch(error).actual, int32_list(datatype));
+            assert_eq!(builder.len(), 0);
+        }
+        let empty = ScalarValue::new_list(&[], &DataType::Int32, true);
+        let mut builder = make_builder(&int64_list(DataType::List), 2).unwrap();
+        let error = builder
+            .append_scalar_value(ScalarValue::List(empty))
+            .unwrap_err();
+        assert_eq!(type_mismatch(error).actual, int32_list(DataType::List));
+
+        let datatype = DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+        let mut builder = make_builder(&datatype, 2).unwrap();
+        assert_eq!(builder.data_type(), datatype);
+        let error = builder
+            .append_scalar_value(ScalarValue::Int64(Some(1)))
+            .unwrap_err();
+        assert_eq!(
+            type_mismatch(error),
+            TypeMismatch {
+                expected: datatype,
+                actual: DataType::Int64
+            }
+        );
+    }
+
+    #[test]
+    fn test_builder_rejects_type_parameters() {
+        let mut builder = make_builder(&DataType::Decimal128(10, 2), 4).unwrap();
+        assert_eq!(builder.data_type(), DataType::Decimal128(10, 2));
+        builder
+            .append_scalar_value(ScalarValue::Decimal128(Some(123), 10, 2))
+            .unwrap();
+        builder.append_scalar_value(ScalarValue::Null).unwrap();
+        let error = builder
+            .append_scalar_value(ScalarValue::Decimal128(Some(123), 12, 2))
+            .unwrap_err();
+        assert_eq!(
+            type_mismatch(error),
+            TypeMismatch {
+                expected: DataType::Decimal128(10, 2),
+                actual: DataType::Decimal128(12, 2)
+            }
+        );
+        assert!(builder
+            .append_scalar_n(ScalarValue::Decimal128(Some(123), 10, 3), 2)
+            .is_err());
+        let decimals = Arc::new(
+            Decimal128Array::from(vec![Some(1)])
+                .with_precision_and_scale(10, 4)
+                .unwrap(),
+        ) as ArrayRef;
+        assert!(builder.append_array_slice(&decimals, 0, 1).is_err());
+        assert_eq!(builder.len(), 2);
+
+        let mut builder = make_builder(&DataType::Decimal256(20, 2), 1).unwrap();
+        assert!(builder
+            .append_scalar_value(ScalarValue::Decimal256(None, 20, 3))
+            .is_err());
+
+        let datatype = DataType::Timestamp(
+            datafusion::arrow::datatypes::TimeUnit::Millisecond,
+            Some("UTC".into()),
+        );
+        let mut builder = make_builder(&datatype, 2).unwrap();
+        assert_eq!(builder.data_type(), datatype);
+        builder
+            .append_scalar_value(ScalarValue::TimestampMillisecond(
+                Some(1),
+                Some("UTC".into()),
+            ))
+            .unwrap();
+        let error = builder
+            .append_scalar_value(ScalarValue::TimestampMillisecond(
+                Some(1),
+                Some("+01:00".into()),
+            ))
+            .unwrap_err();
+        assert_eq!(type_mismatch(error).expected, datatype);
+        let error = builder
+            .append_scalar_value(ScalarValue::TimestampMillisecond(Some(1), None))
+            .unwrap_err();
+        assert_eq!(
+            type_mismatch(error).actual,
+            DataType::Timestamp(datafusion::arrow::datatypes::TimeUnit::Millisecond, None)
+        );
+        assert_eq!(
+            &builder.finish(),
+            &(Arc::new(TimestampMillisecondArray::from(vec![1]).with_timezone("UTC")) as ArrayRef)
+        );
+
+        // arrow's decimal and timestamp builders don't report their parameters,
+        // they are appendable with their data type kept next to them
+        let datatype = DataType::Decimal128(10, 4);
+        let mut builder = TypedBuilder::new(
+            datatype.clone(),
+            datafusion::arrow::array::Decimal128Builder::new().with_data_type(datatype.clone()),
+        );
+        let error = builder
+            .append_scalar_value(ScalarValue::Decimal128(Some(12345), 5, 2))
+            .unwrap_err();
+        assert_eq!(
+            type_mismatch(error),
+            TypeMismatch {
+                expected: datatype,
+                actual: DataType::Decimal128(5, 2)
+            }
+        );
+        builder
+            .append_scalar_value(ScalarValue::Decimal128(Some(12345), 10, 4))
+            .unwrap();
+        assert_eq!(
+            builder.finish().as_ref(),
+            &Decimal128Array::from(vec![12345])
+                .with_precision_and_scale(10, 4)
+                .unwrap() as &dyn Array
+        );
+    }
+
+    #[test]
+    fn test_builder_append_cast() {
+        use datafusion::arrow::compute::CastOptions;
+        use datafusion::arrow::datatypes::TimeUnit;
+
+        let safe = CastOptions::default();
+        let unsafe_options = CastOptions {
+            safe: false,
+            ..Default::default()
+        };
+
+        let mut builder = make_builder(&DataType::Int64, 4).unwrap();
+        assert!(builder
+            .append_scalar_value(ScalarValue::Int32(Some(1)))
+            .is_err());
+        builder
+            .append_scalar_value_cast(ScalarValue::Int32(Some(1)), &unsafe_options)
+            .unwrap();
+        builder
+            .append_scalar_value_cast(ScalarValue::Utf8(Some("2".to_string())), &unsafe_options)
+            .unwrap();
+        builder
+            .append_scalar_value_cast(ScalarValue::Null, &unsafe_options)
+            .unwrap();
+        assert_eq!(
+            &builder.finish(),
+            &create_array(vec![Some(1), Some(2), None])
+        );
+
+        // an overflow is a null with safe options and an error without
+        let mut builder = make_builder(&DataType::Int8, 2).unwrap();
+        builder
+            .append_scalar_value_cast(ScalarValue::Int64(Some(1000)), &safe)
+            .unwrap();
+        assert!(builder
+            .append_scalar_value_cast(ScalarValue::Int64(Some(1000)), &unsafe_options)
+            .is_err());
+        assert_eq!(builder.len(), 1);
+        assert!(builder.finish().is_null(0));
+
+        let datatype = DataType::Decimal128(5, 2);
+        let mut builder = make_builder(&datatype, 2).unwrap();
+        builder
+            .append_scalar_value_cast(ScalarValue::Decimal128(Some(1234), 10, 3), &unsafe_options)
+            .unwrap();
+        assert!(builder
+            .append_scalar_value_cast(
+                ScalarValue::Decimal128(Some(123_456_789), 10, 2),
+                &unsafe_options
+            )
+            .is_err());
+        assert_eq!(
+            &builder.finish(),
+            &(Arc::new(
+                Decimal128Array::from(vec![123])
+                    .with_precision_and_scale(5, 2)
+                    .unwrap()
+            ) as ArrayRef)
+        );
+
+        let datatype = DataType::Timestamp(TimeUnit::Millisecond, None);
+        let mut builder = make_builder(&datatype, 1).unwrap();
+        builder
+            .append_scalar_value_cast(ScalarValue::TimestampSecond(Some(2), None), &safe)
+            .unwrap();
+        assert_eq!(
+            &builder.finish(),
+            &(Arc::new(TimestampMillisecondArray::from(vec![2000])) as ArrayRef)
+        );
+
+        let datatype = DataType::RunEndEncoded(
+            Arc::new(datafusion::arrow::datatypes::Field::new(
+                "run_ends",
+                DataType::Int32,
+                false,
+            )),
+            Arc::new(datafusion::arrow::datatypes::Field::new(
+                "values",
+                DataType::Int64,
+                true,
+            )),
+        );
+        let mut builder = make_builder(&datatype, 2).unwrap();
+        builder
+            .append_scalar_value_cast(ScalarValue::Int32(Some(1)), &safe)
+            .unwrap();
+        builder
+            .append_scalar_value_cast(ScalarValue::Int16(Some(1)), &safe)
+            .unwrap();
+        let result = builder.finish();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result.data_type(), &datatype);
+    }
+
+    #[test]
+    fn test_make_builder_for_field() {
+        use datafusion::arrow::compute::CastOptions;
+        use datafusion::arrow::datatypes::{Field, Fields};
+        use std::collections::HashMap;
+
+        let metadata =
+            HashMap::from([("ARROW:extension:name".to_string(), "arrow.uuid".to_string())]);
+        let field = Field::new("id", DataType::Int64, false).with_metadata(metadata.clone());
+        let mut builder = make_builder_for_field(&field, 4).unwrap();
+        assert_eq!(builder.field().as_ref(), &field);
+        builder
+            .append_scalar_value(ScalarValue::Int64(Some(1)))
+            .unwrap();
+        assert!(builder.append_scalar_value(ScalarValue::Null).is_err());
+        assert!(builder
+            .append_scalar_value(ScalarValue::Int64(None))
+            .is_err());
+        assert!(builder.append_nulls(1).is_err());
+        builder.append_nulls(0).unwrap();
+        let array = create_array(vec![Some(2), None, Some(3)]);
+        assert!(builder.append_array_slice(&array, 0, 2).is_err());
+        builder.append_array_slice(&array, 2, 1).unwrap();
+        assert!(builder
+            .append_scalar_value_cast(
+                ScalarValue::Utf8(Some("x".to_string())),
+                &CastOptions::default()
+            )
+            .is_err());
+        assert_eq!(&builder.finish(), &create_array(vec![Some(1), Some(3)]));
+
+        let nullable = Field::new("id", DataType::Int64, true);
+        let mut builder = make_builder_for_field(&nullable, 1).unwrap();
+        builder.append_scalar_value(ScalarValue::Null).unwrap();
+        assert_eq!(builder.finish().null_count(), 1);
+
+        // metadata of nested fields is kept in the data type of the finished array
+        let key = Field::new("key", DataType::Utf8, false).with_metadata(metadata.clone());
+        let value = Field::new("value", DataType::Int64, true);
+        let entries = Field::new(
+            "entries",
+            DataType::Struct(Fields::from(vec![key, value])),
+            false,
+        );
+        let map = DataType::Map(Arc::new(entries), false);
+        let datatype = DataType::Struct(Fields::from(vec![
+            Field::new("tag", DataType::Utf8, true).with_metadata(metadata),
+            Field::new("attributes", map, true),
+        ]));
+        let field = Field::new("event", datatype.clone(), true);
+        let mut builder = make_builder_for_field(&field, 2).unwrap();
+        builder.append_scalar_value(ScalarValue::Null).unwrap();
+        let result = builder.finish();
+        assert_eq!(result.data_type(), &datatype);
+
+        // non-nullable nested fields reject a null under a valid parent, a null parent masks them.
+        // The arrays allow nulls in x, the field of the builder doesn't.
+        let point = Fields::from(vec![
+            Field::new("x", DataType::Int64, false),
+            Field::new("y", DataType::Int64, true),
+        ]);
+        let field = Field::new("point", DataType::Struct(point), true);
+        let struct_array = |x: Vec<Option<i64>>, valid: Vec<bool>| -> ArrayRef {
+            use datafusion::arrow::array::StructArray;
+            use datafusion::arrow::buffer::NullBuffer;
+
+            let fields = Fields::from(vec![
+                Field::new("x", DataType::Int64, true),
+                Field::new("y", DataType::Int64, true),
+            ]);
+            let y = create_array(vec![None; x.len()]);
+            Arc::new(StructArray::new(
+                fields,
+                vec![create_array(x), y],
+                Some(NullBuffer::from(valid)),
+            ))
+        };
+        let mut builder = make_builder_for_field(&field, 4).unwrap();
+        let array = struct_array(vec![Some(1), None, None], vec![true, false, true]);
+        builder.append_array_slice(&array, 0, 2).unwrap();
+        assert!(builder.append_array_slice(&array, 1, 2).is_err());
+        let invalid = ScalarValue::try_from_array(&array, 2).unwrap();
+        assert!(builder.append_scalar_value(invalid.clone()).is_err());
+        assert!(builder.append_scalar_n(invalid, 2).is_err());
+        builder.append_nulls(1).unwrap();
+        let result = builder.finish();
+        assert_eq!(result.len(), 3);
+        assert_eq!(result.null_count(), 2);
+
+        let item = Field::new("item", DataType::Int64, false);
+        let field = Field::new("values", DataType::List(Arc::new(item)), true);
+        let mut builder = make_builder_for_field(&field, 2).unwrap();
+        let list = create_list_array(vec![Some(vec![Some(1)]), None, Some(vec![Some(2), None])]);
+        builder.append_array_slice(&list, 0, 2).unwrap();
+        let error = builder
+            .append_scalar_value(ScalarValue::try_from_array(&list, 2).unwrap())
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("non-nullable field item"),
+            "{error}"
+        );
+        assert_eq!(builder.finish().len(), 2);
+    }
+
+    #[test]
+    fn test_greatest_index() {
+        let input = create_column_values(vec![
+            vec![Some(1), Some(5), None, Some(2)],
+            vec![Some(3), Some(5), None, None],
+            vec![Some(3), Some(4), None, Some(1)],
+        ]);
+
+        let udf = GreatestIndexUDF::new();
+        let result = udf.invoke(&input).unwrap().into_array(4).unwrap();
+        assert_eq!(
+            result.as_ref(),
+            &datafusion::arrow::array::Int32Array::from(vec![Some(2), Some(1), None, Some(1)])
+                as &dyn Array
+        );
+
+        let udf = GreatestIndexUDF::new()
+            .with_tie_break(TieBreak::Last)
+            .with_index_base(IndexBase::Zero);
+        let result = udf.invoke(&input).unwrap().into_array(4).unwrap();
+        assert_eq!(
+            result.as_ref(),
+            &datafusion::arrow::array::Int32Array::from(vec![Some(2), Some(1), None, Some(0)])
+                as &dyn Array
+        );
+
+        let scalars = vec![
+            ColumnarValue::Scalar(ScalarValue::Int64(None)),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(2))),
+        ];
+        let ColumnarValue::Scalar(result) = GreatestIndexUDF::new().invoke(&scalars).unwrap()
+        else {
+            panic!("expected a scalar result");
+        };
+        assert_eq!(result, ScalarValue::Int32(Some(2)));
+
+        // arguments of different types are compared in their common type
+        let mixed = vec![
+            ColumnarValue::Array(Arc::new(datafusion::arrow::array::Int32Array::from(vec![
+                1, 5,
+            ]))),
+            ColumnarValue::Array(create_array(vec![Some(3), Some(2)])),
+        ];
+        let result = GreatestIndexUDF::new().invoke(&mixed).unwrap();
+        assert_eq!(
+            result.into_array(2).unwrap().as_ref(),
+            &datafusion::arrow::array::Int32Array::from(vec![2, 1]) as &dyn Array
+        );
+    }
+
+    #[tokio::test]
+    async fn test_greatest_index_sql() -> Result<()> {
+        let ctx = SessionContext::new();
+        ctx.register_udf(ScalarUDF::from(GreatestIndexUDF::new()));
+        ctx.register_udf(ScalarUDF::from(GreatestByUDF::new()));
+        ctx.register_csv("data", "tests/data/data.csv", CsvReadOptions::new())
+            .await?;
+
+        let result = ctx
+            .sql(
+                "SELECT argmax(c, b, 5.5) AS position, \
+                 greatest_by('c', 'b', 'literal', c, b, 5.5) AS channel FROM data",
+            )
+            .await?
+            .collect()
+            .await?;
+
+        assert_eq!(
+            result[0].columns()[0].as_ref(),
+            &datafusion::arrow::array::Int32Array::from(vec![3, 1, 1]) as &dyn Array
+        );
+        assert_eq!(
+            result[0].columns()[1].as_ref(),
+            &StringArray::from(vec!["literal", "c", "c"]) as &dyn Array
+        );
+
+        // literal keys pick the same argument in every row of the column values
+        let result = ctx
+            .sql(
+                "SELECT greatest_by(a, 0, 1, 2) AS by_literals, argmax(1, 2) AS position FROM data",
+            )
+            .await?
+            .collect()
+            .await?;
+        assert_eq!(
+            result[0].columns()[0].as_ref(),
+            &Int64Array::from(vec![0, 0, 0]) as &dyn Array
+        );
+        assert_eq!(
+            result[0].columns()[1].as_ref(),
+            &datafusion::arrow::array::Int32Array::from(vec![2, 2, 2]) as &dyn Array
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_greatest_index_and_by_ansi() {
+        let five = || ColumnarValue::Scalar(ScalarValue::Utf8(Some("5".to_string())));
+        let args = vec![
+            ColumnarValue::Array(create_array(vec![Some(3), Some(7)])),
+            five(),
+        ];
+        let error = spark_error(GreatestIndexUDF::new().invoke(&args).unwrap_err());
+        assert_eq!(error.error_class(), "DATATYPE_MISMATCH.DATA_DIFF_TYPES");
+        let result = GreatestIndexUDF::new()
+            .with_ansi(true)
+            .invoke(&args)
+            .unwrap();
+        assert_eq!(
+            result.into_array(2).unwrap().as_ref(),
+            &datafusion::arrow::array::Int32Array::from(vec![2, 1]) as &dyn Array
+        );
+
+        let by_keys = [
+            vec![
+                ColumnarValue::Scalar(ScalarValue::Utf8(Some("a".to_string()))),
+                ColumnarValue::Scalar(ScalarValue::Utf8(Some("b".to_string()))),
+            ],
+            args,
+        ]
+        .concat();
+        let error = spark_error(GreatestByUDF::new().invoke(&by_keys).unwrap_err());
+        assert_eq!(error.error_class(), "DATATYPE_MISMATCH.DATA_DIFF_TYPES");
+        let result = GreatestByUDF::new()
+            .with_ansi(true)
+            .invoke(&by_keys)
+            .unwrap();
+        assert_eq!(
+            result.into_array(2).unwrap().as_ref(),
+            &StringArray::from(vec!["b", "a"]) as &dyn Array
+        );
+    }
+
+    #[test]
+    fn test_greatest_by() {
+        let values = vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![
+                Some("a1"),
+                Some("a2"),
+                Some("a3"),
+            ]))),
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some("b".to_string()))),
+        ];
+        let keys = create_column_values(vec![
+            vec![Some(1), Some(2), None],
+            vec![Some(0), Some(2), None],
+        ]);
+        let args = [values, keys].concat();
+
+        let result = GreatestByUDF::new().invoke(&args).unwrap();
+        assert_eq!(
+            result.into_array(3).unwrap().as_ref(),
+            &StringArray::from(vec![Some("a1"), Some("a2"), None]) as &dyn Array
+        );
+
+        let result = GreatestByUDF::new()
+            .with_tie_break(TieBreak::Last)
+            .invoke(&args)
+            .unwrap();
+        assert_eq!(
+            result.into_array(3).unwrap().as_ref(),
+            &StringArray::from(vec![Some("a1"), Some("b"), None]) as &dyn Array
+        );
+
+        // values and keys are cast to their common types
+        let mixed = vec![
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some("a".to_string()))),
+            ColumnarValue::Scalar(ScalarValue::LargeUtf8(Some("b".to_string()))),
+            ColumnarValue::Array(Arc::new(datafusion::arrow::array::Int32Array::from(vec![
+                1, 5,
+            ]))),
+            ColumnarValue::Array(create_array(vec![Some(3), Some(2)])),
+        ];
+        let result = GreatestByUDF::new().invoke(&mixed).unwrap();
+        assert_eq!(
+            result.into_array(2).unwrap().as_ref(),
+            &datafusion::arrow::array::LargeStringArray::from(vec!["b", "a"]) as &dyn Array
+        );
+
+        let error = spark_error(GreatestByUDF::new().invoke(&args[..3]).unwrap_err());
+        assert_eq!(error.error_class(), "WRONG_NUM_ARGS.WITHOUT_SUGGESTION");
+        assert_eq!(error.parameter("actualNum"), Some("3"));
+        let error = spark_error(
+            GreatestByUDF::new()
+                .return_type(&[DataType::Utf8, DataType::Int64, DataType::Int32])
+                .unwrap_err(),
+        );
+        assert_eq!(
+            error.parameter("expectedNum"),
+            Some("a positive even number of")
+        );
+        let error = spark_error(
+            GreatestByUDF::new()
+                .return_type(&[
+                    DataType::Utf8,
+                    DataType::Int64,
+                    DataType::Int32,
+                    DataType::Utf8,
+                ])
+                .unwrap_err(),
+        );
+        assert_eq!(error.error_class(), "DATATYPE_MISMATCH.DATA_DIFF_TYPES");
+        assert_eq!(error.parameter("dataType"), Some(r#"["STRING", "BIGINT"]"#));
+        assert_eq!(
+            GreatestByUDF::new()
+                .coerce_types(&[
+                    DataType::Utf8,
+                    DataType::LargeUtf8,
+                    DataType::Int64,
+                    DataType::Int32
+                ])
+                .unwrap(),
+            vec![
+                DataType::LargeUtf8,
+                DataType::LargeUtf8,
+                DataType::Int64,
+                DataType::Int64
+            ]
+        );
+    }
+
+    #[test]
+    fn test_greatest_collation() {
+        use datafusion::arrow::array::{LargeStringArray, StringViewArray};
+
+        let strings = |values: Vec<Option<&str>>| {
+            ColumnarValue::Array(Arc::new(StringArray::from(values)) as ArrayRef)
+        };
+        let input = vec![
+            strings(vec![Some("apple"), Some("a"), Some("b  "), None]),
+            strings(vec![Some("Banana"), Some("A"), Some("b"), Some("x ")]),
+        ];
+
+        let greatest_with = |collation: Collation, args: &[ColumnarValue]| {
+            GreatestUDF::new()
+                .with_collation(collation)
+                .invoke(args)
+                .unwrap()
+                .into_array(4)
+                .unwrap()
+        };
+        let expected = |values: Vec<Option<&str>>| Arc::new(StringArray::from(values)) as ArrayRef;
+
+        assert_eq!(
+            &greatest_with(Collation::Binary, &input),
+            &expected(vec![Some("apple"), Some("a"), Some("b  "), Some("x ")])
+        );
+        // equal strings keep the first argument, the result is one of the inputs as they are
+        assert_eq!(
+            &greatest_with(Collation::AsciiCaseInsensitive, &input),
+            &expected(vec![Some("Banana"), Some("a"), Some("b  "), Some("x ")])
+        );
+        assert_eq!(
+            &greatest_with(Collation::Lowercase, &input),
+            &expected(vec![Some("Banana"), Some("a"), Some("b  "), Some("x ")])
+        );
+        assert_eq!(
+            &greatest_with(Collation::Rtrim, &input),
+            &expected(vec![Some("apple"), Some("a"), Some("b  "), Some("x ")])
+        );
+
+        let unicode = vec![
+            strings(vec![Some("Ébène")]),
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some("ébène2".to_string()))),
+        ];
+        let result = GreatestUDF::new()
+            .with_collation(Collation::AsciiCaseInsensitive)
+            .invoke(&unicode)
+            .unwrap()
+            .into_array(1)
+            .unwrap();
+        assert_eq!(&result, &expected(vec![Some("ébène2")]));
+        let result = LeastUDF::new()
+            .with_collation(Collation::Lowercase)
+            .invoke(&unicode)
+            .unwrap()
+            .into_array(1)
+            .unwrap();
+        assert_eq!(&result, &expected(vec![Some("Ébène")]));
+
+        // large and view strings keep their type, including a scalar argument
+        let large = vec![
+            ColumnarValue::Array(Arc::new(LargeStringArray::from(vec![Some("apple"), None]))),
+            ColumnarValue::Scalar(ScalarValue::LargeUtf8(Some("Banana".to_string()))),
+        ];
+        assert_eq!(
+            &greatest_with(Collation::AsciiCaseInsensitive, &large),
+            &(Arc::new(LargeStringArray::from(vec!["Banana", "Banana"])) as ArrayRef)
+        );
+        let view = vec![
+            ColumnarValue::Array(Arc::new(StringViewArray::from(vec![Some("apple"), None]))),
+            ColumnarValue::Array(Arc::new(StringViewArray::from(vec![
+                Some("Banana"),
+                Some("b"),
+            ]))),
+        ];
+        assert_eq!(
+            &greatest_with(Collation::AsciiCaseInsensitive, &view),
+            &(Arc::new(StringViewArray::from(vec!["Banana", "b"])) as ArrayRef)
+        );
+
+        // nested strings go through the generic loop with the same collation
+        let list = |value: &str| {
+            let values = Arc::new(StringArray::from(vec![value])) as ArrayRef;
+            ScalarValue::List(Arc::new(datafusion::arrow::array::ListArray::new(
+                Arc::new(datafusion::arrow::datatypes::Field::new(
+                    "item",
+                    DataType::Utf8,
+                    true,
+                )),
+                datafusion::arrow::buffer::OffsetBuffer::from_lengths([1]),
+                values,
+                None,
+            )))
+        };
+        let args = vec![
+            ColumnarValue::Array(list("b").to_array().unwrap()),
+            ColumnarValue::Array(list("B ").to_array().unwrap()),
+        ];
+        let result = GreatestUDF::new()
+            .with_collation(Collation::AsciiCaseInsensitive)
+            .invoke(&args)
+            .unwrap()
+            .into_array(1)
+            .unwrap();
+        assert_eq!(&result, &list("B ").to_array().unwrap());
+
+        // the winning argument is found with the same collation
+        let result = GreatestIndexUDF::new()
+            .with_collation(Collation::AsciiCaseInsensitive)
+            .invoke(&input)
+            .unwrap();
+        assert_eq!(
+            result.into_array(4).unwrap().as_ref(),
+            &datafusion::arrow::array::Int32Array::from(vec![Some(2), Some(1), Some(1), Some(2)])
+                as &dyn Array
+        );
+        let by_keys = [
+            vec![
+                ColumnarValue::Scalar(ScalarValue::Int64(Some(1))),
+                ColumnarValue::Scalar(ScalarValue::Int64(Some(2))),
+            ],
+            input,
+        ]
+        .concat();
+        let result = GreatestByUDF::new()
+            .with_collation(Collation::AsciiCaseInsensitive)
+            .invoke(&by_keys)
+            .unwrap();
+        assert_eq!(
+            &result.into_array(4).unwrap(),
+            &create_array(vec![Some(2), Some(1), Some(1), Some(2)])
+        );
+    }
+
+    fn semver_comparator(
+        left: &ArrayRef,
+        l: usize,
+        right: &ArrayRef,
+        r: usize,
+    ) -> std::cmp::Ordering {
+        use datafusion::arrow::array::AsArray;
+
+        let version = |array: &ArrayRef, i: usize| {
+            array
+                .as_string::<i32>()
+                .value(i)
+                .split('.')
+                .map(|part| part.parse::<u64>().unwrap_or(0))
+                .collect::<Vec<_>>()
+        };
+        version(left, l).cmp(&version(right, r))
+    }
+
+    #[test]
+    fn test_rowwise_extremum_comparator() {
+        let udf = RowwiseExtremumUDF::new("greatest_semver", semver_comparator);
+        let input = vec![
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![
+                Some("1.10.0"),
+                None,
+                Some("2.0"),
+                None,
+            ]))),
+            ColumnarValue::Array(Arc::new(StringArray::from(vec![
+                Some("1.9.3"),
+                Some("0.1"),
+                Some("2.0.0"),
+                None,
+            ]))),
+        ];
+        let result = udf.invoke(&input).unwrap().into_array(4).unwrap();
+        assert_eq!(
+            result.as_ref(),
+            &StringArray::from(vec![Some("1.10.0"), Some("0.1"), Some("2.0.0"), None])
+                as &dyn Array
+        );
+
+        let scalars = vec![
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some("10.0".to_string()))),
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some("9.9".to_string()))),
+        ];
+        let ColumnarValue::Scalar(result) = udf.invoke(&scalars).unwrap() else {
+            panic!("expected a scalar result");
+        };
+        assert_eq!(result, ScalarValue::Utf8(Some("10.0".to_string())));
+
+        let same = udf.clone();
+        let other = RowwiseExtremumUDF::new("greatest_semver", semver_comparator);
+        assert!(udf.equals(&same));
+        assert!(!udf.equals(&other));
+
+        // the comparator gets the arguments cast to their common type
+        let udf = RowwiseExtremumUDF::new("greatest_int", |left, i, right, j| {
+            use datafusion::arrow::array::AsArray;
+            use datafusion::arrow::datatypes::Int64Type;
+            let left = left.as_primitive::<Int64Type>().value(i);
+            left.cmp(&right.as_primitive::<Int64Type>().value(j))
+        });
+        let input = vec![
+            ColumnarValue::Array(Arc::new(datafusion::arrow::array::Int32Array::from(vec![
+                1, 5,
+            ]))),
+            ColumnarValue::Array(create_array(vec![Some(3), Some(2)])),
+        ];
+        let result = udf.invoke(&input).unwrap().into_array(2).unwrap();
+        assert_eq!(&result, &create_array(vec![Some(3), Some(5)]));
+    }
+
+    #[tokio::test]
+    async fn test_rowwise_extremum_sql() -> Result<()> {
+        let ctx = SessionContext::new();
+        ctx.register_udf(ScalarUDF::from(RowwiseExtremumUDF::new(
+            "greatest_semver",
+            semver_comparator,
+        )));
+        ctx.register_udf(ScalarUDF::from(RowwiseExtremumUDF::new(
+            "least_semver",
+            |l: &ArrayRef, i: usize, r: &ArrayRef, j: usize| semver_comparator(r, j, l, i),
+        )));
+
+        let result = ctx
+            .sql(
+                "SELECT greatest_semver(v, '1.2') AS greatest, least_semver(v, '1.2') AS least \
+                 FROM (VALUES ('1.10'), ('1.1'), (NULL)) AS t(v)",
+            )
+            .await?
+            .collect()
+            .await?;
+        assert_eq!(
+            result[0].columns()[0].as_ref(),
+            &StringArray::from(vec!["1.10", "1.2", "1.2"]) as &dyn Array
+        );
+        assert_eq!(
+            result[0].columns()[1].as_ref(),
+            &StringArray::from(vec!["1.2", "1.1", "1.2"]) as &dyn Array
+        );
+
+        Ok(())
+    }
+
+    struct RowwiseSum;
+
+    impl RowwiseReducer for RowwiseSum {
+        fn name(&self) -> &str {
+            "rowwise_sum"
+        }
+
+        fn fold(&self, acc: Option<&ScalarValue>, value: &ScalarValue) -> Result<Fold> {
+            match acc {
+                None => Ok(Fold::Take),
+                Some(acc) => Ok(Fold::Value(acc.add(value)?)),
+            }
+        }
+    }
+
+    struct CountNotNull;
+
+    impl RowwiseReducer for CountNotNull {
+        fn name(&self) -> &str {
+            "count_not_null"
+        }
+
+        fn return_type(&self, _arg_types: &[DataType]) -> Result<DataType> {
+            Ok(DataType::Int64)
+        }
+
+        fn fold(&self, acc: Option<&ScalarValue>, _value: &ScalarValue) -> Result<Fold> {
+            let count = match acc {
+                Some(ScalarValue::Int64(Some(count))) => *count,
+                _ => 0,
+            };
+            Ok(Fold::Value(ScalarValue::Int64(Some(count + 1))))
+        }
+
+        fn empty(&self, _return_type: &DataType) -> Result<ScalarValue> {
+            Ok(ScalarValue::Int64(Some(0)))
+        }
+
+        fn coerce_types(&self, arg_types: &[DataType]) -> Result<Vec<DataType>> {
+            Ok(arg_types.to_vec())
+        }
+    }
+
+    struct FirstNotNull;
+
+    impl RowwiseReducer for FirstNotNull {
+        fn name(&self) -> &str {
+            "first_not_null"
+        }
+
+        fn fold(&self, acc: Option<&ScalarValue>, _value: &ScalarValue) -> Result<Fold> {
+            Ok(match acc {
+                None => Fold::Take,
+                Some(_) => Fold::Keep,
+            })
+        }
+    }
+
+    #[test]
+    fn test_reduce_rowwise() -> Result<()> {
+        use datafusion::arrow::array::Int32Array;
+
+        let args = vec![
+            ColumnarValue::Array(create_array(vec![Some(1), None, None, Some(4)])),
+            ColumnarValue::Scalar(ScalarValue::Int64(Some(10))),
+            ColumnarValue::Array(create_array(vec![Some(2), Some(3), None, None])),
+        ];
+        let arrays = |result: ColumnarValue| match result {
+            ColumnarValue::Array(array) => array,
+            ColumnarValue::Scalar(_) => panic!("expected an array"),
+        };
+
+        assert_eq!(
+            arrays(reduce_rowwise(&RowwiseSum, &args)?).as_ref(),
+            &Int64Array::from(vec![Some(13), Some(13), Some(10), Some(14)]) as &dyn Array
+        );
+        assert_eq!(
+            arrays(reduce_rowwise(&CountNotNull, &args)?).as_ref(),
+            &Int64Array::from(vec![3, 2, 1, 2]) as &dyn Array
+        );
+        assert_eq!(
+            arrays(reduce_rowwise(&FirstNotNull, &args)?).as_ref(),
+            &Int64Array::from(vec![1, 10, 10, 4]) as &dyn Array
+        );
+
+        // all-null rows get the empty result, literals give a literal
+        let nulls = vec![
+            ColumnarValue::Array(create_array(vec![None, Some(5)])),
+            ColumnarValue::Array(create_array(vec![None, None])),
+        ];
+        assert_eq!(
+            arrays(reduce_rowwise(&RowwiseSum, &nulls)?).as_ref(),
+            &Int64Array::from(vec![None, Some(5)]) as &dyn Array
+        );
+        assert_eq!(
+            arrays(reduce_rowwise(&CountNotNull, &nulls)?).as_ref(),
+            &Int64Array::from(vec![0, 1]) as &dyn Array
+        );
+        let ColumnarValue::Scalar(sum) = reduce_rowwise(
+            &RowwiseSum,
+            &[
+                ColumnarValue::Scalar(ScalarValue::Int64(Some(1))),
+                ColumnarValue::Scalar(ScalarValue::Int64(None)),
+                ColumnarValue::Scalar(ScalarValue::Int64(Some(2))),
+            ],
+        )?
+        else {
+            panic!("expected a scalar");
+        };
+        assert_eq!(sum, ScalarValue::Int64(Some(3)));
+
+        // arguments are cast to the return type, so the taken values have it
+        let mixed = vec![
+            ColumnarValue::Array(Arc::new(Int32Array::from(vec![Some(1), None]))),
+            ColumnarValue::Array(create_array(vec![Some(2), Some(3)])),
+        ];
+        assert_eq!(
+            arrays(reduce_rowwise(&FirstNotNull, &mixed)?).as_ref(),
+            &Int64Array::from(vec![1, 3]) as &dyn Array
+        );
+        // unless the reducer keeps the argument types
+        let ColumnarValue::Scalar(count) = reduce_rowwise(
+            &CountNotNull,
+            &[
+                ColumnarValue::Scalar(ScalarValue::Int64(Some(1))),
+                ColumnarValue::Scalar(ScalarValue::Utf8(Some("a".to_string()))),
+            ],
+        )?
+        else {
+            panic!("expected a scalar");
+        };
+        assert_eq!(count, ScalarValue::Int64(Some(2)));
+
+        assert!(reduce_rowwise(&RowwiseSum, &[]).is_err());
+        assert!(reduce_rowwise(
+            &RowwiseSum,
+            &[
+                ColumnarValue::Scalar(ScalarValue::Int64(Some(1))),
+                ColumnarValue::Scalar(ScalarValue::Utf8(Some("a".to_string()))),
+            ]
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rowwise_reducer_udf() -> Result<()> {
+        let ctx = SessionContext::new();
+        ctx.register_udf(ScalarUDF::from(RowwiseReducerUDF::new(FirstNotNull)));
+        ctx.register_udf(ScalarUDF::from(RowwiseReducerUDF::new(CountNotNull)));
+
+        let result = ctx
+            .sql(
+                "SELECT first_not_null(a, b, 7) AS first, count_not_null(a, b, 'x') AS count \
+                 FROM (VALUES (CAST(NULL AS INT), CAST(2 AS BIGINT)), (1, NULL), (NULL, NULL)) AS t(a, b)",
+            )
+            .await?
+            .collect()
+            .await?;
+        assert_eq!(
+            result[0].columns()[0].as_ref(),
+            &Int64Array::from(vec![2, 1, 7]) as &dyn Array
+        );
+        assert_eq!(
+            result[0].columns()[1].as_ref(),
+            &Int64Array::from(vec![2, 2, 1]) as &dyn Array
+        );
+
+        let error = ctx.sql("SELECT first_not_null(1, 'a')").await.unwrap_err();
+        assert_eq!(
+            spark_error(error).error_class(),
+            "DATATYPE_MISMATCH.DATA_DIFF_TYPES"
+        );
+
+        // calls of the same function are equal, other functions of the same name are not
+        let udf = RowwiseReducerUDF::new(FirstNotNull);
+        assert!(udf.equals(&udf.clone()));
+        assert_eq!(udf.hash_value(), udf.clone().hash_value());
+        assert!(!udf.equals(&RowwiseReducerUDF::new(FirstNotNull)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_spark_error() {
+        let error = spark_error(greatest(&create_column_values(vec![vec![Some(1)]])).unwrap_err());
+        assert_eq!(error.sql_state(), "42605");
+        assert_eq!(error.parameter("actualNum"), Some("1"));
+        assert_eq!(
+            error.to_string(),
+            "[WRONG_NUM_ARGS.WITHOUT_SUGGESTION] The `greatest` requires > 1 parameters but the actual number is 1. \
+             Please, refer to 'https://spark.apache.org/docs/latest/sql-ref-functions.html' for a fix. SQLSTATE: 42605"
+        );
+
+        let error = spark_error(
+            validate_args_types(&[
+                DataType::Int32,
+                DataType::Utf8,
+                DataType::new_list(DataType::Decimal128(10, 2), true),
+            ])
+            .unwrap_err(),
+        );
+        assert_eq!(
+            error.parameter("dataType"),
+            Some(r#"["INT", "STRING", "ARRAY<DECIMAL(10,2)>"]"#)
+        );
+        assert_eq!(
+            error.to_string(),
+            r#"[DATATYPE_MISMATCH.DATA_DIFF_TYPES] Cannot resolve "greatest(?, ?, ?)" due to data type mismatch: Input to `greatest` should all be the same type, but it's ["INT", "STRING", "ARRAY<DECIMAL(10,2)>"]. SQLSTATE: 42K09"#
+        );
+
+        // the call is rendered with the arguments when the function is planned from SQL
+        let ctx = SessionContext::new();
+        ctx.register_udf(ScalarUDF::from(GreatestUDF::new()));
+        ctx.register_udf(ScalarUDF::from(GreatestByUDF::new()));
+        let error = ctx
+            .sql("SELECT greatest(a, 'x', NULL) FROM (VALUES (1)) AS t(a)")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            spark_error(error).parameter("sqlExpr"),
+            Some(r#""greatest(t.a, 'x', NULL)""#)
+        );
+        let error = ctx
+            .sql("SELECT greatest_by('v', 1, a, 'k') FROM (VALUES (1)) AS t(a)")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            spark_error(error).parameter("sqlExpr"),
+            Some(r#""greatest_by('v', 1, t.a, 'k')""#)
+        );
+
+        let error: DataFusionError = SparkError::wrong_num_args("least", "> 1", 0).into();
+        assert!(matches!(error, DataFusionError::External(_)));
+    }
 }
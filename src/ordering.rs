@@ -0,0 +1,221 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+use datafusion::arrow::array::{Array, ArrayRef, StructArray};
+use datafusion::arrow::datatypes::{
+    ArrowPrimitiveType, DataType, Float16Type, Float32Type, Float64Type,
+};
+use datafusion::scalar::ScalarValue;
+
+/// How NaN values of Float16/Float32/Float64 arguments are ordered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum NanOrdering {
+    /// Spark semantics: NaN is larger than any other value (including infinity),
+    /// all NaNs are equal to each other and -0.0 is equal to 0.0.
+    #[default]
+    Spark,
+    /// IEEE-754 `maxNum`/`minNum` style: NaN is skipped like a null, so the result is NaN
+    /// only if all non null arguments are NaN.
+    IgnoreNan,
+}
+
+/// How strings (Utf8, LargeUtf8 and Utf8View) are compared. Whatever the collation,
+/// the result of greatest() and least() is one of the original strings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Collation {
+    /// byte-wise comparison, as Spark's `UTF8_BINARY`
+    #[default]
+    Binary,
+    /// ASCII letters are compared case-insensitively, the other characters byte-wise
+    AsciiCaseInsensitive,
+    /// strings are compared by their Unicode lowercase form, as Spark's `UTF8_LCASE`
+    Lowercase,
+    /// trailing spaces are ignored, as if the shorter string was right-padded with spaces
+    /// (Spark's `RTRIM` collations)
+    Rtrim,
+}
+
+impl Collation {
+    /// The string that is compared byte-wise in place of `value`
+    pub(crate) fn key<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        match self {
+            Collation::Binary => Cow::Borrowed(value),
+            Collation::AsciiCaseInsensitive if value.bytes().any(|b| b.is_ascii_uppercase()) => {
+                Cow::Owned(value.to_ascii_lowercase())
+            }
+            Collation::AsciiCaseInsensitive => Cow::Borrowed(value),
+            Collation::Lowercase => Cow::Owned(value.to_lowercase()),
+            Collation::Rtrim => Cow::Borrowed(value.trim_end_matches(' ')),
+        }
+    }
+
+    fn compare(&self, left: &str, right: &str) -> Ordering {
+        self.key(left).cmp(&self.key(right))
+    }
+}
+
+/// Returns true if strings of `datatype` are compared according to a collation
+pub(crate) fn is_collated(datatype: &DataType) -> bool {
+    match datatype {
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => true,
+        DataType::Dictionary(_, value) => is_collated(value),
+        _ => datatype.is_nested(),
+    }
+}
+
+/// Arrow floating point types with Spark's total ordering of their native values.
+pub(crate) trait SparkFloat: ArrowPrimitiveType {
+    fn is_nan(value: Self::Native) -> bool;
+
+    /// the canonical NaN every NaN is mapped to
+    fn nan() -> Self::Native;
+
+    fn total_cmp(left: Self::Native, right: Self::Native) -> Ordering;
+
+    /// Maps every NaN (whatever its sign and payload) to the canonical one and -0.0 to 0.0,
+    /// so that `total_cmp` of the normalized values is the Spark ordering.
+    fn spark_normalize(value: Self::Native) -> Self::Native {
+        if Self::is_nan(value) {
+            Self::nan()
+        } else if value == Self::Native::default() {
+            Self::Native::default()
+        } else {
+            value
+        }
+    }
+
+    fn spark_cmp(left: Self::Native, right: Self::Native) -> Ordering {
+        Self::total_cmp(Self::spark_normalize(left), Self::spark_normalize(right))
+    }
+}
+
+macro_rules! impl_spark_float {
+    ($t:ty) => {
+        impl SparkFloat for $t {
+            fn is_nan(value: Self::Native) -> bool {
+                value.is_nan()
+            }
+
+            fn nan() -> Self::Native {
+                <<$t as ArrowPrimitiveType>::Native>::NAN
+            }
+
+            fn total_cmp(left: Self::Native, right: Self::Native) -> Ordering {
+                left.total_cmp(&right)
+            }
+        }
+    };
+}
+
+impl_spark_float!(Float16Type);
+impl_spark_float!(Float32Type);
+impl_spark_float!(Float64Type);
+
+/// Returns true for not null floating point NaN scalars
+pub(crate) fn is_nan(value: &ScalarValue) -> bool {
+    match value {
+        ScalarValue::Float16(Some(v)) => v.is_nan(),
+        ScalarValue::Float32(Some(v)) => v.is_nan(),
+        ScalarValue::Float64(Some(v)) => v.is_nan(),
+        ScalarValue::Dictionary(_, v) => is_nan(v),
+        _ => false,
+    }
+}
+
+/// Compares two not null scalars of the same type. Floating point numbers, lists and structs are compared
+/// with the Spark ordering, the rest goes through `ScalarValue`'s ordering.
+/// Dictionary scalars are compared by their values.
+pub(crate) fn compare_scalars(left: &ScalarValue, right: &ScalarValue) -> Option<Ordering> {
+    compare_collated(left, right, Collation::Binary)
+}
+
+/// `compare_scalars` where strings, also the ones in lists and structs, are compared with `collation`
+pub(crate) fn compare_collated(
+    left: &ScalarValue,
+    right: &ScalarValue,
+    collation: Collation,
+) -> Option<Ordering> {
+    match (left, right) {
+        (ScalarValue::Dictionary(_, l), ScalarValue::Dictionary(_, r)) => {
+            compare_collated(l, r, collation)
+        }
+        (ScalarValue::List(l), ScalarValue::List(r)) => {
+            compare_lists(&l.value(0), &r.value(0), collation)
+        }
+        (ScalarValue::LargeList(l), ScalarValue::LargeList(r)) => {
+            compare_lists(&l.value(0), &r.value(0), collation)
+        }
+        (ScalarValue::FixedSizeList(l), ScalarValue::FixedSizeList(r)) => {
+            compare_lists(&l.value(0), &r.value(0), collation)
+        }
+        (ScalarValue::Struct(l), ScalarValue::Struct(r)) => compare_structs(l, r, collation),
+        (ScalarValue::Utf8(Some(l)), ScalarValue::Utf8(Some(r)))
+        | (ScalarValue::LargeUtf8(Some(l)), ScalarValue::LargeUtf8(Some(r)))
+        | (ScalarValue::Utf8View(Some(l)), ScalarValue::Utf8View(Some(r))) => {
+            Some(collation.compare(l, r))
+        }
+        (ScalarValue::Float16(Some(l)), ScalarValue::Float16(Some(r))) => {
+            Some(Float16Type::spark_cmp(*l, *r))
+        }
+        (ScalarValue::Float32(Some(l)), ScalarValue::Float32(Some(r))) => {
+            Some(Float32Type::spark_cmp(*l, *r))
+        }
+        (ScalarValue::Float64(Some(l)), ScalarValue::Float64(Some(r))) => {
+            Some(Float64Type::spark_cmp(*l, *r))
+        }
+        (l, r) => l.partial_cmp(r),
+    }
+}
+
+/// Compares two possibly null scalars, a null is smaller than any value (Spark's ascending order)
+pub(crate) fn compare_nulls_first(
+    left: &ScalarValue,
+    right: &ScalarValue,
+    collation: Collation,
+) -> Option<Ordering> {
+    match (left.is_null(), right.is_null()) {
+        (true, true) => Some(Ordering::Equal),
+        (true, false) => Some(Ordering::Less),
+        (false, true) => Some(Ordering::Greater),
+        (false, false) => compare_collated(left, right, collation),
+    }
+}
+
+/// Spark's struct ordering: fields are compared one by one in their order,
+/// a null field is smaller than any value of that field.
+fn compare_structs(
+    left: &StructArray,
+    right: &StructArray,
+    collation: Collation,
+) -> Option<Ordering> {
+    if left.num_columns() != right.num_columns() {
+        return None;
+    }
+
+    for (l, r) in left.columns().iter().zip(right.columns()) {
+        let l = ScalarValue::try_from_array(l, 0).ok()?;
+        let r = ScalarValue::try_from_array(r, 0).ok()?;
+        match compare_nulls_first(&l, &r, collation)? {
+            Ordering::Equal => continue,
+            ordering => return Some(ordering),
+        }
+    }
+
+    Some(Ordering::Equal)
+}
+
+/// Spark's array ordering, used for List, LargeList and FixedSizeList.
+/// Elements are compared one by one, a null element is smaller than any value.
+/// If one list is a prefix of the other, the shorter one is smaller.
+fn compare_lists(left: &ArrayRef, right: &ArrayRef, collation: Collation) -> Option<Ordering> {
+    for i in 0..left.len().min(right.len()) {
+        let l = ScalarValue::try_from_array(left, i).ok()?;
+        let r = ScalarValue::try_from_array(right, i).ok()?;
+        match compare_nulls_first(&l, &r, collation)? {
+            Ordering::Equal => continue,
+            ordering => return Some(ordering),
+        }
+    }
+
+    Some(left.len().cmp(&right.len()))
+}
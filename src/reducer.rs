@@ -0,0 +1,199 @@
+use datafusion::arrow::compute::cast_with_options;
+use datafusion::arrow::datatypes::DataType;
+use datafusion::common::format::DEFAULT_CAST_OPTIONS;
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_expr::ColumnarValue;
+use datafusion::scalar::ScalarValue;
+
+use crate::builder::{make_builder, scalar_from_array, AppendableBuilder};
+use crate::coercion::spark_common_type;
+use crate::error::{unknown_args, SparkError};
+
+/// What a reducer does with the next not null value of a row
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fold {
+    /// the accumulator stays as it is, the value is dropped
+    Keep,
+    /// the value becomes the accumulator. The result is then copied from the argument,
+    /// so it must have the return type
+    Take,
+    /// the accumulator is replaced with a computed value
+    Value(ScalarValue),
+}
+
+/// A row-wise reduction of the arguments into one column, e.g. greatest(), a row-wise sum or the first not null value.
+/// `reduce_rowwise` runs the reduction: it skips nulls, handles literal arguments and builds the result
+/// with `make_builder`, so an implementation only folds the values of a row.
+pub trait RowwiseReducer: Send + Sync {
+    /// Name of the reduction in error messages
+    fn name(&self) -> &str;
+
+    /// Type of the result, the common type of the arguments by default
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
+        spark_common_type(arg_types, false).ok_or_else(|| {
+            SparkError::data_diff_types(self.name(), &unknown_args(arg_types.len()), arg_types)
+                .into()
+        })
+    }
+
+    /// Types the arguments are cast to before their values are folded, the return type for all of them by default,
+    /// so `Fold::Take` copies values of the return type. A reducer that doesn't take argument values
+    /// (e.g. a count) can keep the argument types.
+    fn coerce_types(&self, arg_types: &[DataType]) -> Result<Vec<DataType>> {
+        Ok(vec![self.return_type(arg_types)?; arg_types.len()])
+    }
+
+    /// Folds the not null `value` of the next argument into the accumulator of a row.
+    /// `acc` is None until a value is taken or computed. Arguments are folded in their order.
+    fn fold(&self, acc: Option<&ScalarValue>, value: &ScalarValue) -> Result<Fold>;
+
+    /// Result of a row without an accumulator (e.g. all arguments are null), null by default
+    fn empty(&self, _return_type: &DataType) -> Result<ScalarValue> {
+        Ok(ScalarValue::Null)
+    }
+
+    /// Vectorized fast path: returns the result computed with arrow kernels, or None for the generic loop.
+    /// The arguments are already cast to `coerce_types`.
+    fn reduce_arrays(&self, _args: &[ColumnarValue]) -> Result<Option<ColumnarValue>> {
+        Ok(None)
+    }
+}
+
+/// Where the result of a row comes from
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Winner {
+    Null,
+    Arg(usize),
+    Value(ScalarValue),
+}
+
+/// Appends the value of every row's winner to the builder, `winners` yields one winner per row.
+/// Consecutive rows won by the same argument are copied to the builder as one slice.
+pub(crate) fn append_winners<T: AppendableBuilder>(
+    builder: &mut T,
+    winners: impl Iterator<Item = Result<Winner>>,
+    args: &[ColumnarValue],
+) -> Result<()> {
+    let mut append_run = |winner: &Winner, start: usize, len: usize| match winner {
+        Winner::Null => builder.append_nulls(len),
+        Winner::Value(value) if value.is_null() => builder.append_nulls(len),
+        Winner::Value(value) => builder.append_scalar_n(value.clone(), len),
+        Winner::Arg(arg) => match &args[*arg] {
+            ColumnarValue::Array(array) => builder.append_array_slice(array, start, len),
+            ColumnarValue::Scalar(value) => builder.append_scalar_n(value.clone(), len),
+        },
+    };
+
+    let mut rows = 0;
+    let mut run: Option<(Winner, usize)> = None;
+    for (i, winner) in winners.enumerate() {
+        let winner = winner?;
+        match &run {
+            Some((current, _)) if current == &winner => {}
+            Some((current, start)) => {
+                append_run(current, *start, i - start)?;
+                run = Some((winner, i));
+            }
+            None => run = Some((winner, i)),
+        }
+        rows = i + 1;
+    }
+    if let Some((winner, start)) = run {
+        append_run(&winner, start, rows - start)?;
+    }
+    Ok(())
+}
+
+/// Number of rows of the arguments, 1 if all of them are scalars
+pub(crate) fn rows(args: &[ColumnarValue]) -> usize {
+    args.iter()
+        .find_map(|arg| match arg {
+            ColumnarValue::Array(array) => Some(array.len()),
+            ColumnarValue::Scalar(_) => None,
+        })
+        .unwrap_or(1)
+}
+
+/// Casts an argument to `data_type` unless it already has it, failing on values that can't be cast
+pub(crate) fn cast_arg(arg: &ColumnarValue, data_type: &DataType) -> Result<ColumnarValue> {
+    if &arg.data_type() == data_type {
+        return Ok(arg.clone());
+    }
+    Ok(match arg {
+        ColumnarValue::Array(array) => {
+            ColumnarValue::Array(cast_with_options(array, data_type, &DEFAULT_CAST_OPTIONS)?)
+        }
+        ColumnarValue::Scalar(value) => ColumnarValue::Scalar(value.cast_to(data_type)?),
+    })
+}
+
+/// Runs `reducer` over the rows of `args`. Arguments are cast to the reducer's `coerce_types` first,
+/// before the fast path and the generic loop. Literal arguments are never expanded to arrays,
+/// the result is a scalar if all arguments are scalars.
+pub fn reduce_rowwise<R: RowwiseReducer + ?Sized>(
+    reducer: &R,
+    args: &[ColumnarValue],
+) -> Result<ColumnarValue> {
+    if args.is_empty() {
+        return Err(SparkError::wrong_num_args(reducer.name(), "> 0", 0).into());
+    }
+    let types = args.iter().map(|arg| arg.data_type()).collect::<Vec<_>>();
+    let return_type = reducer.return_type(&types)?;
+    let coerced = reducer.coerce_types(&types)?;
+    if coerced.len() != args.len() {
+        return Err(DataFusionError::Internal(format!(
+            "{}() coerced {} arguments to {} types",
+            reducer.name(),
+            args.len(),
+            coerced.len()
+        )));
+    }
+    let args = coerced
+        .iter()
+        .zip(args)
+        .map(|(data_type, arg)| cast_arg(arg, data_type))
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(result) = reducer.reduce_arrays(&args)? {
+        return Ok(result);
+    }
+
+    let empty = reducer.empty(&return_type)?;
+    let rows = rows(&args);
+
+    let winners = (0..rows).map(|i| {
+        let mut acc: Option<ScalarValue> = None;
+        let mut winner = None;
+        for (arg, value) in args.iter().enumerate() {
+            let value = match value {
+                ColumnarValue::Array(array) => scalar_from_array(array, i)?,
+                ColumnarValue::Scalar(value) => value.clone(),
+            };
+            if value.is_null() {
+                continue;
+            }
+            match reducer.fold(acc.as_ref(), &value)? {
+                Fold::Keep => {}
+                Fold::Take => {
+                    acc = Some(value);
+                    winner = Some(Winner::Arg(arg));
+                }
+                Fold::Value(value) => {
+                    winner = Some(Winner::Value(value.clone()));
+                    acc = Some(value);
+                }
+            }
+        }
+        Ok(winner.unwrap_or_else(|| Winner::Value(empty.clone())))
+    });
+    let mut builder = make_builder(&return_type, rows)?;
+    append_winners(&mut builder, winners, &args)?;
+    let result = builder.finish();
+
+    if args
+        .iter()
+        .all(|arg| matches!(arg, ColumnarValue::Scalar(_)))
+    {
+        return Ok(ColumnarValue::Scalar(scalar_from_array(&result, 0)?));
+    }
+    Ok(ColumnarValue::Array(result))
+}
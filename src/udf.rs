@@ -1,38 +1,846 @@
+use std::any::Any;
+use std::cmp::Ordering;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use datafusion::arrow::array::{ArrayRef, Int32Array};
+
+use datafusion::common::ExprSchema;
 use datafusion::error::Result;
-use datafusion::logical_expr::ColumnarValue;
+use datafusion::logical_expr::expr::ScalarFunction;
+use datafusion::logical_expr::interval_arithmetic::Interval;
+use datafusion::logical_expr::simplify::{ExprSimplifyResult, SimplifyInfo};
+use datafusion::logical_expr::sort_properties::{ExprProperties, SortProperties};
+use datafusion::logical_expr::{Cast, ColumnarValue, Expr, ScalarUDF};
+use datafusion::scalar::ScalarValue;
 use datafusion::{
-    arrow::datatypes::DataType,
+    arrow::datatypes::{DataType, Field},
     logical_expr::{ScalarUDFImpl, Signature, TypeSignature, Volatility},
 };
 
-use crate::greatest::{greatest, validate_args_types};
+use crate::bounds::{extremum_bounds, propagate_extremum_constraints};
+use crate::coercion::spark_common_type;
+use crate::error::{unknown_args, SparkError};
+use crate::greatest::{
+    arg_extremum, common_type, common_type_of_args, extremum, extremum_by,
+    extremum_with_comparator, result_nullable, split_values_and_keys, validate_args_count,
+    ExtremumOptions, IndexBase, NullHandling, RowComparator, TieBreak,
+};
+use crate::ordering::{is_collated, Collation, NanOrdering};
+use crate::reducer::{reduce_rowwise, rows, RowwiseReducer};
+
+/// Which extremum an `ExtremumUDF` computes, implemented by the `Greatest` and `Least` markers
+pub trait ExtremumKind: std::fmt::Debug + Clone + Send + Sync + 'static {
+    /// the ordering a new value must have against the current one to replace it
+    const TARGET: Ordering;
+    /// SQL name of the function that skips nulls
+    const NAME: &'static str;
+    /// SQL name of the function that propagates nulls
+    const STRICT_NAME: &'static str;
+}
+
+/// greatest() of PySpark, see `GreatestUDF`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Greatest;
+
+impl ExtremumKind for Greatest {
+    const TARGET: Ordering = Ordering::Greater;
+    const NAME: &'static str = "greatest";
+    const STRICT_NAME: &'static str = "greatest_strict";
+}
+
+/// least() of PySpark, see `LeastUDF`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Least;
+
+impl ExtremumKind for Least {
+    const TARGET: Ordering = Ordering::Less;
+    const NAME: &'static str = "least";
+    const STRICT_NAME: &'static str = "least_strict";
+}
+
+/// ExtremumUDF is a user-defined function that analogues to the greatest() or least() function in PySpark,
+/// the function is selected by `T`. Use it through the `GreatestUDF` and `LeastUDF` aliases.
+#[derive(Debug, Clone)]
+pub struct ExtremumUDF<T: ExtremumKind> {
+    name: &'static str,
+    signature: Signature,
+    options: ExtremumOptions,
+    kind: PhantomData<T>,
+}
 
 /// GreatestUDF is a user-defined function that analogues to the greatest() function in PySpark.
-#[derive(Debug)]
-pub struct GreatestUDF;
+pub type GreatestUDF = ExtremumUDF<Greatest>;
 
-impl ScalarUDFImpl for GreatestUDF {
-    fn as_any(&self) -> &dyn std::any::Any {
+/// LeastUDF is a user-defined function that analogues to the least() function in PySpark.
+pub type LeastUDF = ExtremumUDF<Least>;
+
+impl<T: ExtremumKind> ExtremumUDF<T> {
+    /// Creates the function with Spark semantics: null arguments are skipped.
+    pub const fn new() -> Self {
+        Self::new_with_null_handling(NullHandling::Skip)
+    }
+
+    /// Creates the function with the given null handling. Each mode has its own SQL name,
+    /// so both of them can be registered in the same context: `greatest`/`least` for [`NullHandling::Skip`]
+    /// and `greatest_strict`/`least_strict` for [`NullHandling::Propagate`].
+    pub const fn new_with_null_handling(null_handling: NullHandling) -> Self {
+        let name = match null_handling {
+            NullHandling::Skip => T::NAME,
+            NullHandling::Propagate => T::STRICT_NAME,
+        };
+        Self {
+            name,
+            signature: Signature {
+                type_signature: TypeSignature::UserDefined,
+                volatility: Volatility::Immutable,
+            },
+            options: ExtremumOptions {
+                nan_ordering: NanOrdering::Spark,
+                null_handling,
+                collation: Collation::Binary,
+                ansi: false,
+            },
+            kind: PhantomData,
+        }
+    }
+
+    /// Selects how NaN values are ordered, the Spark ordering is used by default.
+    pub fn with_nan_ordering(mut self, nan_ordering: NanOrdering) -> Self {
+        self.options.nan_ordering = nan_ordering;
+        self
+    }
+
+    /// Selects how strings are compared, they are compared byte-wise by default.
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.options.collation = collation;
+        self
+    }
+
+    /// Selects the type widening rules of `spark.sql.ansi.enabled=true`, where a string argument is
+    /// cast to the type of the other arguments. They are off by default.
+    pub fn with_ansi(mut self, ansi: bool) -> Self {
+        self.options.ansi = ansi;
+        self
+    }
+
+    /// Field-aware version of `return_type`: the output field has the common type of the
+    /// arguments and its nullability is derived from the argument fields (see [`NullHandling`]).
+    ///
+    /// This is not a `ScalarUDFImpl` hook: DataFusion 41 never calls it and marks every scalar function result
+    /// as nullable, also in the schemas of `ctx.sql` and `DataFrame` plans. It takes effect in plans only once
+    /// the DataFusion dependency has a field-aware return hook; until then call it yourself to build
+    /// the output schema when the nullability matters (e.g. required Parquet columns).
+    pub fn return_field(&self, arg_fields: &[Field]) -> Result<Field> {
+        let types = arg_fields
+            .iter()
+            .map(|field| field.data_type().clone())
+            .collect::<Vec<_>>();
+        let nullable = arg_fields.iter().map(|field| field.is_nullable());
+        Ok(Field::new(
+            self.name,
+            common_type(self.name, &types, self.options.ansi)?,
+            result_nullable(self.options.null_handling, nullable),
+        ))
+    }
+}
+
+impl<T: ExtremumKind> Default for ExtremumUDF<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ExtremumKind> ScalarUDFImpl for ExtremumUDF<T> {
+    fn as_any(&self) -> &dyn Any {
         self
     }
 
     fn name(&self) -> &str {
-        "greatest"
+        self.name
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The result has the common type of the arguments. With [`NullHandling::Skip`] the result
+    /// is null only if all arguments are null, with [`NullHandling::Propagate`] it is null if any
+    /// argument is null. See `return_field` for the nullability of the result.
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
+        common_type(self.name, arg_types, self.options.ansi)
+    }
+
+    /// Same as `return_type`, the arguments render the call in the error of incomparable types
+    fn return_type_from_exprs(
+        &self,
+        args: &[Expr],
+        _schema: &dyn ExprSchema,
+        arg_types: &[DataType],
+    ) -> Result<DataType> {
+        common_type_of_args(self.name, &args_sql(args), arg_types, self.options.ansi)
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        extremum(self.name, args, T::TARGET, self.options)
+    }
+
+    fn simplify(&self, args: Vec<Expr>, info: &dyn SimplifyInfo) -> Result<ExprSimplifyResult> {
+        simplify_extremum(self, args, info, T::TARGET, self.options)
+    }
+
+    fn evaluate_bounds(&self, inputs: &[&Interval]) -> Result<Interval> {
+        let datatype = inputs[0].data_type();
+        if collated(self.options, &datatype) {
+            return Interval::make_unbounded(&datatype);
+        }
+        extremum_bounds(inputs, T::TARGET, self.options.null_handling)
     }
 
-    fn signature(&self) -> &datafusion::logical_expr::Signature {
-        &Signature {
-            type_signature: TypeSignature::VariadicAny,
-            volatility: Volatility::Immutable,
+    fn propagate_constraints(
+        &self,
+        interval: &Interval,
+        inputs: &[&Interval],
+    ) -> Result<Option<Vec<Interval>>> {
+        if collated(self.options, &interval.data_type()) {
+            return Ok(Some(inputs.iter().map(|input| (*input).clone()).collect()));
         }
+        propagate_extremum_constraints(interval, inputs, T::TARGET, self.options.null_handling)
     }
 
+    fn output_ordering(&self, inputs: &[ExprProperties]) -> Result<SortProperties> {
+        Ok(extremum_ordering(inputs, T::TARGET, self.options))
+    }
+
+    fn coerce_types(&self, arg_types: &[DataType]) -> Result<Vec<DataType>> {
+        Ok(coerce_arguments(arg_types, self.options.ansi))
+    }
+
+    fn equals(&self, other: &dyn ScalarUDFImpl) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<Self>()
+            .is_some_and(|other| self.name() == other.name() && self.options == other.options)
+    }
+
+    fn hash_value(&self) -> u64 {
+        hash_udf(self.name(), self.options)
+    }
+}
+
+/// GreatestIndexUDF returns the position of the argument with the greatest value in each row
+/// (`greatest_index`, also registered as `argmax`). Arguments are compared as in greatest() and null
+/// arguments are skipped, the result is null only if all arguments are null.
+#[derive(Debug, Clone)]
+pub struct GreatestIndexUDF {
+    signature: Signature,
+    aliases: Vec<String>,
+    options: ExtremumOptions,
+    tie_break: TieBreak,
+    index_base: IndexBase,
+}
+
+impl GreatestIndexUDF {
+    /// Creates `greatest_index` returning 1-based positions, the first of equal arguments wins.
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::user_defined(Volatility::Immutable),
+            aliases: vec!["argmax".to_string()],
+            options: ExtremumOptions::default(),
+            tie_break: TieBreak::default(),
+            index_base: IndexBase::default(),
+        }
+    }
+
+    /// Selects which of the equal arguments wins a row
+    pub fn with_tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.tie_break = tie_break;
+        self
+    }
+
+    /// Selects whether the positions start from 0 or from 1
+    pub fn with_index_base(mut self, index_base: IndexBase) -> Self {
+        self.index_base = index_base;
+        self
+    }
+
+    /// Selects how NaN values are ordered, the Spark ordering is used by default.
+    pub fn with_nan_ordering(mut self, nan_ordering: NanOrdering) -> Self {
+        self.options.nan_ordering = nan_ordering;
+        self
+    }
+
+    /// Selects how strings are compared, they are compared byte-wise by default.
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.options.collation = collation;
+        self
+    }
+
+    /// Selects the type widening rules of `spark.sql.ansi.enabled=true`, where a string argument is
+    /// cast to the type of the other arguments. They are off by default.
+    pub fn with_ansi(mut self, ansi: bool) -> Self {
+        self.options.ansi = ansi;
+        self
+    }
+
+    fn position(&self, position: usize) -> i32 {
+        match self.index_base {
+            IndexBase::Zero => position as i32,
+            IndexBase::One => position as i32 + 1,
+        }
+    }
+}
+
+impl Default for GreatestIndexUDF {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScalarUDFImpl for GreatestIndexUDF {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "greatest_index"
+    }
+
+    fn aliases(&self) -> &[String] {
+        &self.aliases
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The position is an `Int32`, the arguments must have a common type to be compared.
     fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
-        validate_args_types(arg_types)?;
-        Ok(arg_types[0].clone())
+        common_type(self.name(), arg_types, self.options.ansi)?;
+        Ok(DataType::Int32)
+    }
+
+    fn return_type_from_exprs(
+        &self,
+        args: &[Expr],
+        _schema: &dyn ExprSchema,
+        arg_types: &[DataType],
+    ) -> Result<DataType> {
+        common_type_of_args(self.name(), &args_sql(args), arg_types, self.options.ansi)?;
+        Ok(DataType::Int32)
     }
 
     fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
-        greatest(args).map(ColumnarValue::Array)
+        validate_args_count(self.name(), args.len())?;
+        let positions = arg_extremum(
+            self.name(),
+            args,
+            rows(args),
+            Ordering::Greater,
+            self.options,
+            self.tie_break,
+        )?;
+        if args
+            .iter()
+            .all(|arg| matches!(arg, ColumnarValue::Scalar(_)))
+        {
+            let position = positions[0].map(|position| self.position(position));
+            return Ok(ColumnarValue::Scalar(ScalarValue::Int32(position)));
+        }
+        let positions = positions
+            .into_iter()
+            .map(|position| position.map(|position| self.position(position)));
+        Ok(ColumnarValue::Array(Arc::new(Int32Array::from_iter(
+            positions,
+        ))))
+    }
+
+    fn coerce_types(&self, arg_types: &[DataType]) -> Result<Vec<DataType>> {
+        Ok(coerce_arguments(arg_types, self.options.ansi))
+    }
+
+    fn equals(&self, other: &dyn ScalarUDFImpl) -> bool {
+        other.as_any().downcast_ref::<Self>().is_some_and(|other| {
+            self.options == other.options
+                && self.tie_break == other.tie_break
+                && self.index_base == other.index_base
+        })
+    }
+
+    fn hash_value(&self) -> u64 {
+        hash_udf(self.name(), (self.options, self.tie_break, self.index_base))
+    }
+}
+
+/// GreatestByUDF takes values followed by the same number of keys, `greatest_by(v1, v2, k1, k2)`,
+/// and returns the value of the greatest key in each row (`v1` if `k1` is the greatest).
+/// Keys are compared as in greatest(), the result is null if all keys are null.
+#[derive(Debug, Clone)]
+pub struct GreatestByUDF {
+    signature: Signature,
+    options: ExtremumOptions,
+    tie_break: TieBreak,
+}
+
+impl GreatestByUDF {
+    /// Creates `greatest_by`, the value of the first of equal keys is returned.
+    pub fn new() -> Self {
+        Self {
+            signature: Signature::user_defined(Volatility::Immutable),
+            options: ExtremumOptions::default(),
+            tie_break: TieBreak::default(),
+        }
+    }
+
+    /// Selects which of the equal keys wins a row
+    pub fn with_tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.tie_break = tie_break;
+        self
     }
+
+    /// Selects how NaN keys are ordered, the Spark ordering is used by default.
+    pub fn with_nan_ordering(mut self, nan_ordering: NanOrdering) -> Self {
+        self.options.nan_ordering = nan_ordering;
+        self
+    }
+
+    /// Selects how string keys are compared, they are compared byte-wise by default.
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.options.collation = collation;
+        self
+    }
+
+    /// Selects the type widening rules of `spark.sql.ansi.enabled=true`, where a string value or key is
+    /// cast to the type of the other values or keys. They are off by default.
+    pub fn with_ansi(mut self, ansi: bool) -> Self {
+        self.options.ansi = ansi;
+        self
+    }
+
+    /// common types of the values and of the keys, `args` render the call in the error of incomparable types
+    fn common_types(
+        &self,
+        args: &[String],
+        arg_types: &[DataType],
+    ) -> Result<(DataType, DataType)> {
+        let (values, keys) = split_values_and_keys(self.name(), arg_types)?;
+        let common = |types: &[DataType]| {
+            spark_common_type(types, self.options.ansi)
+                .ok_or_else(|| SparkError::data_diff_types(self.name(), args, types))
+        };
+        Ok((common(values)?, common(keys)?))
+    }
+}
+
+impl Default for GreatestByUDF {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScalarUDFImpl for GreatestByUDF {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "greatest_by"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The result has the common type of the values
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
+        self.common_types(&unknown_args(arg_types.len()), arg_types)
+            .map(|(values, _)| values)
+    }
+
+    fn return_type_from_exprs(
+        &self,
+        args: &[Expr],
+        _schema: &dyn ExprSchema,
+        arg_types: &[DataType],
+    ) -> Result<DataType> {
+        self.common_types(&args_sql(args), arg_types)
+            .map(|(values, _)| values)
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        extremum_by(
+            self.name(),
+            args,
+            Ordering::Greater,
+            self.options,
+            self.tie_break,
+        )
+    }
+
+    /// The values are cast to their common type and the keys to theirs
+    fn coerce_types(&self, arg_types: &[DataType]) -> Result<Vec<DataType>> {
+        let Ok((values, keys)) = self.common_types(&unknown_args(arg_types.len()), arg_types)
+        else {
+            return Ok(arg_types.to_vec());
+        };
+        let half = arg_types.len() / 2;
+        Ok([vec![values; half], vec![keys; half]].concat())
+    }
+
+    fn equals(&self, other: &dyn ScalarUDFImpl) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<Self>()
+            .is_some_and(|other| self.options == other.options && self.tie_break == other.tie_break)
+    }
+
+    fn hash_value(&self) -> u64 {
+        hash_udf(self.name(), (self.options, self.tie_break))
+    }
+}
+
+/// RowwiseExtremumUDF is greatest() with a user ordering of the values, registered under its own SQL name
+/// (e.g. `greatest_semver` comparing version strings). Arguments are cast to their common type as in greatest(),
+/// null arguments are skipped and the first of equal arguments wins.
+/// least() with a user ordering is this function with the reversed comparator.
+#[derive(Clone)]
+pub struct RowwiseExtremumUDF {
+    name: String,
+    signature: Signature,
+    comparator: RowComparator,
+}
+
+impl RowwiseExtremumUDF {
+    /// Creates the function `name` that orders values with `comparator`
+    pub fn new(
+        name: impl Into<String>,
+        comparator: impl Fn(&ArrayRef, usize, &ArrayRef, usize) -> Ordering + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            signature: Signature::user_defined(Volatility::Immutable),
+            comparator: Arc::new(comparator),
+        }
+    }
+}
+
+impl std::fmt::Debug for RowwiseExtremumUDF {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RowwiseExtremumUDF")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ScalarUDFImpl for RowwiseExtremumUDF {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The result has the common type of the arguments
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
+        common_type(&self.name, arg_types, false)
+    }
+
+    fn return_type_from_exprs(
+        &self,
+        args: &[Expr],
+        _schema: &dyn ExprSchema,
+        arg_types: &[DataType],
+    ) -> Result<DataType> {
+        common_type_of_args(&self.name, &args_sql(args), arg_types, false)
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        extremum_with_comparator(&self.name, args, &self.comparator)
+    }
+
+    fn coerce_types(&self, arg_types: &[DataType]) -> Result<Vec<DataType>> {
+        Ok(coerce_arguments(arg_types, false))
+    }
+
+    /// Functions are equal only if they share the comparator, closures can't be compared
+    fn equals(&self, other: &dyn ScalarUDFImpl) -> bool {
+        other.as_any().downcast_ref::<Self>().is_some_and(|other| {
+            self.name == other.name && Arc::ptr_eq(&self.comparator, &other.comparator)
+        })
+    }
+
+    fn hash_value(&self) -> u64 {
+        hash_udf(&self.name, ())
+    }
+}
+
+/// RowwiseReducerUDF registers a `RowwiseReducer` as a scalar function under the reducer's name.
+/// Arguments are cast to the reducer's `coerce_types` and the rows are reduced with `reduce_rowwise`.
+pub struct RowwiseReducerUDF<R> {
+    signature: Signature,
+    reducer: Arc<R>,
+}
+
+impl<R: RowwiseReducer> RowwiseReducerUDF<R> {
+    /// Creates the function that runs `reducer`
+    pub fn new(reducer: R) -> Self {
+        Self {
+            signature: Signature::user_defined(Volatility::Immutable),
+            reducer: Arc::new(reducer),
+        }
+    }
+
+    /// The reduction run by the function
+    pub fn reducer(&self) -> &R {
+        &self.reducer
+    }
+}
+
+impl<R> Clone for RowwiseReducerUDF<R> {
+    fn clone(&self) -> Self {
+        Self {
+            signature: self.signature.clone(),
+            reducer: self.reducer.clone(),
+        }
+    }
+}
+
+impl<R: RowwiseReducer> std::fmt::Debug for RowwiseReducerUDF<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RowwiseReducerUDF")
+            .field("name", &self.reducer.name())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R: RowwiseReducer + 'static> ScalarUDFImpl for RowwiseReducerUDF<R> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        self.reducer.name()
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, arg_types: &[DataType]) -> Result<DataType> {
+        self.reducer.return_type(arg_types)
+    }
+
+    fn invoke(&self, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+        reduce_rowwise(self.reducer.as_ref(), args)
+    }
+
+    /// Arguments the reducer can't coerce are left as they are, so `return_type` reports the error
+    fn coerce_types(&self, arg_types: &[DataType]) -> Result<Vec<DataType>> {
+        Ok(self
+            .reducer
+            .coerce_types(arg_types)
+            .unwrap_or_else(|_| arg_types.to_vec()))
+    }
+
+    /// Functions are equal only if they share the reducer, reducers can't be compared
+    fn equals(&self, other: &dyn ScalarUDFImpl) -> bool {
+        other.as_any().downcast_ref::<Self>().is_some_and(|other| {
+            self.name() == other.name() && Arc::ptr_eq(&self.reducer, &other.reducer)
+        })
+    }
+
+    fn hash_value(&self) -> u64 {
+        hash_udf(self.name(), ())
+    }
+}
+
+/// SQL of the arguments in error messages, literals are rendered as in Spark (`1`, `'a'`, `NULL`)
+fn args_sql(args: &[Expr]) -> Vec<String> {
+    args.iter()
+        .map(|arg| match arg {
+            Expr::Literal(value) if value.is_null() => "NULL".to_string(),
+            Expr::Literal(
+                ScalarValue::Utf8(Some(value))
+                | ScalarValue::LargeUtf8(Some(value))
+                | ScalarValue::Utf8View(Some(value)),
+            ) => format!("'{value}'"),
+            Expr::Literal(value) => value.to_string(),
+            arg => arg.to_string(),
+        })
+        .collect()
+}
+
+/// Casts all arguments to their Spark common type. Incomparable arguments are left as they are,
+/// so `return_type` reports the error instead of the planner's generic coercion failure.
+fn coerce_arguments(arg_types: &[DataType], ansi: bool) -> Vec<DataType> {
+    match spark_common_type(arg_types, ansi) {
+        Some(common) => vec![common; arg_types.len()],
+        None => arg_types.to_vec(),
+    }
+}
+
+/// Rewrites greatest()/least() calls:
+/// - nested calls of the same function are flattened, `greatest(greatest(a, b), c)` -> `greatest(a, b, c)`
+/// - null literals are dropped when nulls are skipped, or turn the whole call into null when they are propagated
+/// - duplicate arguments are removed, `greatest(a, a, b)` -> `greatest(a, b)` (volatile ones are kept)
+/// - calls with only literal arguments are folded to a literal
+/// - a single remaining argument becomes a cast of that argument to the result type
+fn simplify_extremum<U: ScalarUDFImpl + Clone + 'static>(
+    udf: &U,
+    args: Vec<Expr>,
+    info: &dyn SimplifyInfo,
+    target: Ordering,
+    options: ExtremumOptions,
+) -> Result<ExprSimplifyResult> {
+    let types = args
+        .iter()
+        .map(|arg| info.get_data_type(arg))
+        .collect::<Result<Vec<_>>>()?;
+    // incomparable arguments are reported by `return_type`, there is nothing to simplify
+    let Ok(return_type) = udf.return_type(&types) else {
+        return Ok(ExprSimplifyResult::Original(args));
+    };
+
+    let mut flat = Vec::with_capacity(args.len());
+    for arg in &args {
+        match arg {
+            Expr::ScalarFunction(ScalarFunction { func, args: inner })
+                if udf.equals(func.inner().as_ref()) =>
+            {
+                flat.extend(inner.iter().cloned())
+            }
+            arg => flat.push(arg.clone()),
+        }
+    }
+
+    let mut simplified: Vec<Expr> = Vec::with_capacity(flat.len());
+    for arg in flat {
+        if matches!(&arg, Expr::Literal(value) if value.is_null()) {
+            match options.null_handling {
+                NullHandling::Skip => continue,
+                NullHandling::Propagate => {
+                    let null = ScalarValue::try_from(&return_type)?;
+                    return Ok(ExprSimplifyResult::Simplified(Expr::Literal(null)));
+                }
+            }
+        }
+        if !arg.is_volatile()? && simplified.contains(&arg) {
+            continue;
+        }
+        simplified.push(arg);
+    }
+
+    if simplified.is_empty() {
+        // all arguments are null literals
+        let null = ScalarValue::try_from(&return_type)?;
+        return Ok(ExprSimplifyResult::Simplified(Expr::Literal(null)));
+    }
+
+    if simplified.len() == 1 {
+        let arg = simplified.remove(0);
+        return Ok(ExprSimplifyResult::Simplified(Expr::Cast(Cast::new(
+            Box::new(arg),
+            return_type,
+        ))));
+    }
+
+    if simplified.iter().all(|arg| matches!(arg, Expr::Literal(_))) {
+        // literals are compared as the common type, as they are when the call is evaluated
+        let literals = simplified
+            .iter()
+            .filter_map(|arg| match arg {
+                Expr::Literal(value) => {
+                    Some(value.cast_to(&return_type).map(ColumnarValue::Scalar))
+                }
+                _ => None,
+            })
+            .collect::<Result<Vec<_>>>()?;
+        // the extremum of scalars is always a scalar
+        if let ColumnarValue::Scalar(value) = extremum(udf.name(), &literals, target, options)? {
+            return Ok(ExprSimplifyResult::Simplified(Expr::Literal(value)));
+        }
+    }
+
+    if simplified == args {
+        return Ok(ExprSimplifyResult::Original(args));
+    }
+    let udf = ScalarUDF::new_from_impl(udf.clone());
+    Ok(ExprSimplifyResult::Simplified(udf.call(simplified)))
+}
+
+/// greatest() and least() are monotonically non-decreasing in each argument, so the result is
+/// sorted if all arguments are sorted in the same direction (literals don't break the order).
+///
+/// Nulls have to be ordered the way the function treats them: when nulls are skipped, a null
+/// argument acts as the smallest value for greatest() and as the largest one for least().
+/// When nulls are propagated, the null rows of the result are the union of the null rows
+/// of the arguments, which are all at the same end.
+///
+/// Strings compared with a non-binary collation are not ordered by the inputs' (binary) sort order.
+/// Neither are floats when NaNs are ignored: NaN is the largest value of a sorted input, but it is
+/// skipped by the function, e.g. `greatest(a, b)` of the sorted `a = [1.0, NaN]` and `b = [NaN, 0.5]` is `[1.0, 0.5]`.
+fn extremum_ordering(
+    inputs: &[ExprProperties],
+    target: Ordering,
+    options: ExtremumOptions,
+) -> SortProperties {
+    if inputs.iter().any(|input| {
+        let datatype = input.range.data_type();
+        collated(options, &datatype)
+            || (options.nan_ordering == NanOrdering::IgnoreNan && is_floating(&datatype))
+    }) {
+        return SortProperties::Unordered;
+    }
+
+    let mut result = SortProperties::Singleton;
+    for input in inputs {
+        match (input.sort_properties, result) {
+            (SortProperties::Singleton, _) => {}
+            (SortProperties::Ordered(sort), SortProperties::Singleton) => {
+                result = SortProperties::Ordered(sort)
+            }
+            (SortProperties::Ordered(sort), SortProperties::Ordered(current))
+                if sort == current => {}
+            _ => return SortProperties::Unordered,
+        }
+    }
+
+    if let SortProperties::Ordered(sort) = result {
+        let nulls_smallest = sort.nulls_first != sort.descending;
+        let nulls_as_skipped = match target {
+            Ordering::Greater => nulls_smallest,
+            _ => !nulls_smallest,
+        };
+        if options.null_handling == NullHandling::Skip && !nulls_as_skipped {
+            return SortProperties::Unordered;
+        }
+    }
+    result
+}
+
+/// Intervals and sort orders are in the binary ordering, which doesn't hold for strings under another collation
+fn collated(options: ExtremumOptions, datatype: &DataType) -> bool {
+    options.collation != Collation::Binary && is_collated(datatype)
+}
+
+/// Returns true for floating point types, also dictionary encoded ones
+fn is_floating(datatype: &DataType) -> bool {
+    match datatype {
+        DataType::Dictionary(_, value) => is_floating(value),
+        _ => datatype.is_floating(),
+    }
+}
+
+/// UDFs with the same name but different options are different functions
+fn hash_udf(name: &str, options: impl Hash) -> u64 {
+    let hasher = &mut DefaultHasher::new();
+    name.hash(hasher);
+    options.hash(hasher);
+    hasher.finish()
 }